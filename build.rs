@@ -18,6 +18,82 @@ impl BuildPaths {
     }
 }
 
+/// Environment variable pointing to a directory with a prebuilt `libwfa.a` or `libwfa.so`
+/// (`libwfa.dylib` on macOS). When it holds one, it is linked instead of building the submodule.
+const WFA2_LIB_DIR_ENV: &str = "WFA2_LIB_DIR";
+
+/// Include and library directories of an OpenMP runtime (`libomp`) for macOS builds.
+struct LibOmp {
+    include_dir: PathBuf,
+    lib_dir: PathBuf,
+}
+
+impl LibOmp {
+    /// Looks for `omp.h` and `libomp` under `prefix`, also in the `libomp` subdirectories
+    /// MacPorts uses.
+    fn from_prefix(prefix: &std::path::Path) -> Option<Self> {
+        let include_dir = [prefix.join("include"), prefix.join("include/libomp")]
+            .into_iter()
+            .find(|dir| dir.join("omp.h").exists())?;
+        let lib_dir = [prefix.join("lib"), prefix.join("lib/libomp")]
+            .into_iter()
+            .find(|dir| dir.join("libomp.dylib").exists() || dir.join("libomp.a").exists())?;
+        Some(Self {
+            include_dir,
+            lib_dir,
+        })
+    }
+}
+
+/// Locates libomp on macOS. In order: the `LIBOMP_PREFIX` environment variable (an install
+/// prefix with `include/omp.h` and `lib/libomp.*`), `brew --prefix libomp`, then the MacPorts
+/// (`/opt/local`), `/usr/local` and `$LLVM_PATH` prefixes. Returns `None` if none has it, in
+/// which case WFA2-lib is built without OpenMP.
+fn find_libomp() -> Option<LibOmp> {
+    if let Some(prefix) = env::var_os("LIBOMP_PREFIX").map(PathBuf::from) {
+        match LibOmp::from_prefix(&prefix) {
+            Some(libomp) => return Some(libomp),
+            None => println!(
+                "cargo:warning=LIBOMP_PREFIX={} holds no omp.h and libomp, searching elsewhere",
+                prefix.display()
+            ),
+        }
+    }
+
+    let brew_prefix = Command::new("brew")
+        .args(["--prefix", "libomp"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()));
+    let mut prefixes: Vec<PathBuf> = brew_prefix.into_iter().collect();
+    prefixes.push(PathBuf::from("/opt/local"));
+    prefixes.push(PathBuf::from("/usr/local"));
+    if let Some(llvm) = env::var_os("LLVM_PATH") {
+        prefixes.push(PathBuf::from(llvm));
+    }
+    prefixes
+        .iter()
+        .find_map(|prefix| LibOmp::from_prefix(prefix))
+}
+
+/// Prebuilt WFA2-lib to link against: its directory and whether it is a static library.
+fn find_system_wfa() -> Option<(PathBuf, bool)> {
+    let dir = PathBuf::from(env::var_os(WFA2_LIB_DIR_ENV)?);
+    if dir.join("libwfa.a").exists() {
+        return Some((dir, true));
+    }
+    if dir.join("libwfa.so").exists() || dir.join("libwfa.dylib").exists() {
+        return Some((dir, false));
+    }
+    println!(
+        "cargo:warning={} is set to {} but it holds no libwfa.a, libwfa.so or libwfa.dylib",
+        WFA2_LIB_DIR_ENV,
+        dir.display()
+    );
+    None
+}
+
 fn setup_compiler_environment() {
     // Set compiler environment variables to override hardcoded paths in WFA2-lib Makefile
     if cfg!(target_os = "macos") {
@@ -33,7 +109,7 @@ fn setup_compiler_environment() {
     }
 }
 
-fn build_wfa() -> Result<(), Box<dyn std::error::Error>> {
+fn build_wfa(libomp: Option<&LibOmp>) -> Result<(), Box<dyn std::error::Error>> {
     let paths = BuildPaths::new();
 
     // Set up compiler environment before doing anything else
@@ -57,27 +133,26 @@ fn build_wfa() -> Result<(), Box<dyn std::error::Error>> {
             "-O3 -mtune=native".to_string()
         };
 
-        // On macOS, find libomp installed by Homebrew to get correct paths
-        let libomp_prefix = String::from_utf8(
-            Command::new("brew")
-                .arg("--prefix")
-                .arg("libomp")
-                .output()?
-                .stdout,
-        )
-        .unwrap()
-        .trim()
-        .to_string();
-
-        // Add the include path for omp.h to CFLAGS
-        cflags.push_str(&format!(" -I{}/include", libomp_prefix));
-        make_cmd.env("CFLAGS", cflags);
+        if let Some(libomp) = libomp {
+            // Add the include path for omp.h to CFLAGS
+            cflags.push_str(&format!(" -I{}", libomp.include_dir.display()));
 
-        // Add the library path for the linker
-        make_cmd.env("LDFLAGS", format!("-L{}/lib", libomp_prefix));
+            // Add the library path for the linker
+            make_cmd.env("LDFLAGS", format!("-L{}", libomp.lib_dir.display()));
 
-        // Explicitly set the correct OpenMP flags for macOS to override Makefile logic.
-        make_cmd.env("OMP_FLAG", "-Xpreprocessor -fopenmp -lomp");
+            // Explicitly set the correct OpenMP flags for macOS to override Makefile logic.
+            make_cmd.env("OMP_FLAG", "-Xpreprocessor -fopenmp -lomp");
+        } else {
+            // Apple's clang has no OpenMP runtime: build the sequential library instead of
+            // failing at link time.
+            println!(
+                "cargo:warning=libomp not found, building WFA2-lib without OpenMP \
+                 (set LIBOMP_PREFIX to use it)"
+            );
+            // Passed on the command line so it overrides the Makefile's own setting.
+            make_cmd.arg("BUILD_WFA_PARALLEL=0");
+        }
+        make_cmd.env("CFLAGS", cflags);
     } else if target.contains("x86_64") {
         make_cmd.env("CFLAGS", "-O3 -march=native");
     } else if target.contains("aarch64") || target.contains("arm") {
@@ -106,46 +181,31 @@ fn build_wfa() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-fn setup_linking() {
-    let paths = BuildPaths::new();
-
+fn setup_linking(wfa_lib_dir: &std::path::Path, static_lib: bool, libomp: Option<&LibOmp>) {
     // Link the WFA library
-    println!("cargo:rustc-link-lib=static=wfa");
+    if static_lib {
+        println!("cargo:rustc-link-lib=static=wfa");
+    } else {
+        println!("cargo:rustc-link-lib=dylib=wfa");
+    }
 
     // On macOS, link against libomp instead of libgomp for the final Rust binary
     let target = env::var("TARGET").unwrap_or_default();
     if target.contains("apple") || cfg!(target_os = "macos") {
-        // Find libomp from Homebrew and add its lib path for rustc to find.
-        let libomp_prefix = String::from_utf8(
-            Command::new("brew")
-                .arg("--prefix")
-                .arg("libomp")
-                .output()
-                .expect("Failed to execute brew command to find libomp")
-                .stdout,
-        )
-        .unwrap()
-        .trim()
-        .to_string();
-        println!("cargo:rustc-link-search=native={}/lib", libomp_prefix);
-
-        println!("cargo:rustc-link-lib=omp");
+        // Add libomp's lib path for rustc to find; without it WFA2-lib was built sequentially.
+        if let Some(libomp) = libomp {
+            println!(
+                "cargo:rustc-link-search=native={}",
+                libomp.lib_dir.display()
+            );
+            println!("cargo:rustc-link-lib=omp");
+        }
     } else {
         println!("cargo:rustc-link-lib=gomp");
     }
 
     // Set library search path for WFA
-    println!(
-        "cargo:rustc-link-search=native={}",
-        paths.wfa_lib_dir().display()
-    );
-
-    // Rerun if WFA library changes
-    println!("cargo:rerun-if-changed=WFA2-lib");
-    println!(
-        "cargo:rerun-if-changed={}/libwfa.a",
-        paths.wfa_lib_dir().display()
-    );
+    println!("cargo:rustc-link-search=native={}", wfa_lib_dir.display());
 
     // Generate bindings
     // let bindings = bindgen::Builder::default()
@@ -173,8 +233,41 @@ fn setup_linking() {
 }
 
 fn main() {
-    if let Err(e) = build_wfa() {
+    println!("cargo:rerun-if-env-changed={}", WFA2_LIB_DIR_ENV);
+    println!("cargo:rerun-if-env-changed=LIBOMP_PREFIX");
+
+    let target = env::var("TARGET").unwrap_or_default();
+    let libomp = if target.contains("apple") || cfg!(target_os = "macos") {
+        find_libomp()
+    } else {
+        None
+    };
+
+    if let Some((dir, static_lib)) = find_system_wfa() {
+        println!(
+            "cargo:warning=Linking prebuilt WFA2-lib from {} ({}), skipping the submodule build",
+            dir.display(),
+            if static_lib { "static" } else { "shared" }
+        );
+        setup_linking(&dir, static_lib, libomp.as_ref());
+        println!("cargo:rerun-if-changed={}", dir.display());
+        return;
+    }
+
+    println!(
+        "cargo:warning=Building the bundled WFA2-lib submodule (set {} to link a prebuilt one)",
+        WFA2_LIB_DIR_ENV
+    );
+    if let Err(e) = build_wfa(libomp.as_ref()) {
         panic!("Failed to build WFA2-lib: {}", e);
     }
-    setup_linking();
+    let paths = BuildPaths::new();
+    setup_linking(&paths.wfa_lib_dir(), true, libomp.as_ref());
+
+    // Rerun if WFA library changes
+    println!("cargo:rerun-if-changed=WFA2-lib");
+    println!(
+        "cargo:rerun-if-changed={}/libwfa.a",
+        paths.wfa_lib_dir().display()
+    );
 }
@@ -0,0 +1,33 @@
+use lib_wfa2::affine_wavefront::Distance;
+
+pub fn main() {
+    println!("Example3\n");
+
+    // pattern means query and text means reference
+    let pairs: Vec<(&[u8], &[u8])> = vec![
+        (
+            b"TCTTTACTCGCGCGTTGGAGAAATACAATAGT",
+            b"TCTATACTGCGCGTTTGGAGAAATAAAATAGT",
+        ),
+        (b"GATTACAGATTACA", b"GATTACAGATTTACA"),
+        (b"ACGTACGTACGT", b"ACGTACGAACGT"),
+    ];
+
+    // Each thread owns its aligner: aligners can be moved across threads, not shared.
+    let handles: Vec<_> = pairs
+        .into_iter()
+        .enumerate()
+        .map(|(i, (pattern, text))| {
+            let aligner = Distance::Edit.create_aligner(None);
+            std::thread::spawn(move || {
+                let status = aligner.align(pattern, text);
+                (i, status, aligner.score())
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        let (i, status, score) = handle.join().unwrap();
+        println!("Pair {}: {:?}, score {}", i, status, score);
+    }
+}
@@ -9,6 +9,11 @@ use core::slice;
 #[derive(Debug, Clone, Copy)]
 pub enum Distance {
     Edit,
+    /// Mismatches cost `mismatch` and every gap base `indel`, with no opening penalty.
+    GapLinear {
+        mismatch: i32,
+        indel: i32,
+    },
     GapAffine {
         mismatch: i32,
         gap_opening: i32,
@@ -23,10 +28,39 @@ pub enum Distance {
     },
 }
 
+/// Penalties of a distance as plain numbers, whatever the metric (see
+/// `AffineWavefronts::get_penalties`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Penalties {
+    pub mismatch: i32,
+    pub gap_opening1: i32,
+    pub gap_extension1: i32,
+    pub gap_opening2: i32,
+    pub gap_extension2: i32,
+}
+
 impl Distance {
+    /// Creates an aligner for this distance.
+    ///
+    /// # Panics
+    ///
+    /// If WFA2-lib fails to allocate the aligner; see `try_create_aligner`.
     pub fn create_aligner(&self, heuristic: Option<&HeuristicStrategy>) -> AffineWavefronts {
+        self.try_create_aligner(heuristic)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Creates an aligner for this distance, or returns an error if WFA2-lib fails to
+    /// allocate it.
+    pub fn try_create_aligner(
+        &self,
+        heuristic: Option<&HeuristicStrategy>,
+    ) -> Result<AffineWavefronts, String> {
         match self {
             Distance::Edit => AffineWavefronts::new_aligner_edit(heuristic),
+            Distance::GapLinear { mismatch, indel } => {
+                AffineWavefronts::new_aligner_gap_linear(*mismatch, *indel, heuristic)
+            }
             Distance::GapAffine {
                 mismatch,
                 gap_opening,
@@ -60,6 +94,7 @@ impl Distance {
             Distance::Edit => 0,
             Distance::GapAffine { .. } => 1,
             Distance::GapAffine2p { .. } => 2,
+            Distance::GapLinear { .. } => 3,
         }
     }
 
@@ -79,9 +114,172 @@ impl Distance {
                 gap_opening2: 0,
                 gap_extension2: 0,
             }),
+            3 => Ok(Distance::GapLinear {
+                mismatch: 0,
+                indel: 0,
+            }),
             _ => Err(format!("Invalid distance code: {}", code)),
         }
     }
+
+    /// Encodes the metric and its penalties: the `to_u8` code followed by the penalties as
+    /// little-endian `i32`s, in declaration order.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let penalties: &[i32] = match self {
+            Distance::Edit => &[],
+            Distance::GapLinear { mismatch, indel } => &[*mismatch, *indel],
+            Distance::GapAffine {
+                mismatch,
+                gap_opening,
+                gap_extension,
+            } => &[*mismatch, *gap_opening, *gap_extension],
+            Distance::GapAffine2p {
+                mismatch,
+                gap_opening1,
+                gap_extension1,
+                gap_opening2,
+                gap_extension2,
+            } => &[
+                *mismatch,
+                *gap_opening1,
+                *gap_extension1,
+                *gap_opening2,
+                *gap_extension2,
+            ],
+        };
+        let mut bytes = vec![self.to_u8()];
+        bytes.extend(penalties.iter().flat_map(|penalty| penalty.to_le_bytes()));
+        bytes
+    }
+
+    /// Decodes a distance written by `to_bytes`; trailing bytes are an error.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = bytes;
+        let distance = Self::read_bytes(&mut reader)?;
+        expect_consumed(reader)?;
+        Ok(distance)
+    }
+
+    /// Decodes a distance written by `to_bytes` from the front of `reader`, advancing it.
+    fn read_bytes(reader: &mut &[u8]) -> Result<Self, String> {
+        Ok(match Self::from_u8(read_u8(reader)?)? {
+            Distance::Edit => Distance::Edit,
+            Distance::GapLinear { .. } => Distance::GapLinear {
+                mismatch: read_i32(reader)?,
+                indel: read_i32(reader)?,
+            },
+            Distance::GapAffine { .. } => Distance::GapAffine {
+                mismatch: read_i32(reader)?,
+                gap_opening: read_i32(reader)?,
+                gap_extension: read_i32(reader)?,
+            },
+            Distance::GapAffine2p { .. } => Distance::GapAffine2p {
+                mismatch: read_i32(reader)?,
+                gap_opening1: read_i32(reader)?,
+                gap_extension1: read_i32(reader)?,
+                gap_opening2: read_i32(reader)?,
+                gap_extension2: read_i32(reader)?,
+            },
+        })
+    }
+
+    /// Penalty of a single mismatch.
+    fn mismatch_penalty(&self) -> i32 {
+        match self {
+            Distance::Edit => 1,
+            Distance::GapLinear { mismatch, .. }
+            | Distance::GapAffine { mismatch, .. }
+            | Distance::GapAffine2p { mismatch, .. } => *mismatch,
+        }
+    }
+
+    /// Penalty of a gap of `length` bases (WFA2-lib charges `gap_opening + length * gap_extension`).
+    fn gap_penalty(&self, length: i32) -> i32 {
+        match self {
+            Distance::Edit => length,
+            Distance::GapLinear { indel, .. } => length * indel,
+            Distance::GapAffine {
+                gap_opening,
+                gap_extension,
+                ..
+            } => gap_opening + length * gap_extension,
+            Distance::GapAffine2p {
+                gap_opening1,
+                gap_extension1,
+                gap_opening2,
+                gap_extension2,
+                ..
+            } => {
+                (gap_opening1 + length * gap_extension1).min(gap_opening2 + length * gap_extension2)
+            }
+        }
+    }
+
+    /// Upper bound on the penalty of a single alignment column: a gap costs at most its first
+    /// base (opening plus extension) per base, and the cheaper gap model bounds affine2p.
+    fn max_column_penalty(&self) -> i32 {
+        let gap = match self {
+            Distance::Edit => 1,
+            Distance::GapLinear { indel, .. } => *indel,
+            Distance::GapAffine {
+                gap_opening,
+                gap_extension,
+                ..
+            } => gap_opening + gap_extension,
+            Distance::GapAffine2p {
+                gap_opening1,
+                gap_extension1,
+                gap_opening2,
+                gap_extension2,
+                ..
+            } => (gap_opening1 + gap_extension1).min(gap_opening2 + gap_extension2),
+        };
+        self.mismatch_penalty().max(gap).max(1)
+    }
+
+    /// Gap models as `(opening, extension)` pairs; a gap costs the cheapest of them.
+    fn gap_models(&self) -> Vec<(i32, i32)> {
+        match *self {
+            Distance::Edit => vec![(0, 1)],
+            Distance::GapLinear { indel, .. } => vec![(0, indel)],
+            Distance::GapAffine {
+                gap_opening,
+                gap_extension,
+                ..
+            } => vec![(gap_opening, gap_extension)],
+            Distance::GapAffine2p {
+                gap_opening1,
+                gap_extension1,
+                gap_opening2,
+                gap_extension2,
+                ..
+            } => vec![
+                (gap_opening1, gap_extension1),
+                (gap_opening2, gap_extension2),
+            ],
+        }
+    }
+
+    /// Total penalty of a CIGAR under this distance.
+    pub(crate) fn cigar_penalty(&self, cigar: &[u8]) -> i32 {
+        cigar
+            .chunk_by(|x, y| x == y)
+            .map(|run| match run[0] {
+                b'X' => run.len() as i32 * self.mismatch_penalty(),
+                b'I' | b'D' => self.gap_penalty(run.len() as i32),
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// Converts a penalty into the score WFA2-lib reports for this distance
+    /// (positive for edit distance, negated for the gap-affine models).
+    pub(crate) fn penalty_to_score(&self, penalty: i32) -> i32 {
+        match self {
+            Distance::Edit => penalty,
+            _ => -penalty,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -116,6 +314,113 @@ pub enum HeuristicStrategy {
     },
 }
 
+impl HeuristicStrategy {
+    /// Fields of `wavefront_heuristic_t` the strategy sets, with their values.
+    ///
+    /// Several strategies share fields (e.g. all cutoff-based ones use `steps_between_cutoffs`),
+    /// which is what limits how they can be combined.
+    fn heuristic_fields(&self) -> Vec<(&'static str, std::os::raw::c_int)> {
+        match *self {
+            HeuristicStrategy::None => vec![],
+            HeuristicStrategy::BandedStatic {
+                band_min_k,
+                band_max_k,
+            } => vec![("min_k", band_min_k), ("max_k", band_max_k)],
+            HeuristicStrategy::BandedAdaptive {
+                band_min_k,
+                band_max_k,
+                score_steps,
+            } => vec![
+                ("min_k", band_min_k),
+                ("max_k", band_max_k),
+                ("steps_between_cutoffs", score_steps),
+            ],
+            HeuristicStrategy::WFAdaptive {
+                min_wavefront_length,
+                max_distance_threshold,
+                score_steps,
+            }
+            | HeuristicStrategy::WFMash {
+                min_wavefront_length,
+                max_distance_threshold,
+                score_steps,
+            } => vec![
+                ("min_wavefront_length", min_wavefront_length),
+                ("max_distance_threshold", max_distance_threshold),
+                ("steps_between_cutoffs", score_steps),
+            ],
+            HeuristicStrategy::XDrop { xdrop, score_steps } => {
+                vec![("xdrop", xdrop), ("steps_between_cutoffs", score_steps)]
+            }
+            HeuristicStrategy::ZDrop { zdrop, score_steps } => {
+                vec![("zdrop", zdrop), ("steps_between_cutoffs", score_steps)]
+            }
+        }
+    }
+
+    /// Encodes the strategy: a tag byte (in declaration order, `None` being 0) followed by its
+    /// parameters as little-endian `i32`s, in declaration order.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let tag = match self {
+            HeuristicStrategy::None => 0,
+            HeuristicStrategy::BandedStatic { .. } => 1,
+            HeuristicStrategy::BandedAdaptive { .. } => 2,
+            HeuristicStrategy::WFAdaptive { .. } => 3,
+            HeuristicStrategy::XDrop { .. } => 4,
+            HeuristicStrategy::ZDrop { .. } => 5,
+            HeuristicStrategy::WFMash { .. } => 6,
+        };
+        let mut bytes = vec![tag];
+        for (_, value) in self.heuristic_fields() {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Decodes a strategy written by `to_bytes`; trailing bytes are an error.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = bytes;
+        let heuristic = Self::read_bytes(&mut reader)?;
+        expect_consumed(reader)?;
+        Ok(heuristic)
+    }
+
+    /// Decodes a strategy written by `to_bytes` from the front of `reader`, advancing it.
+    fn read_bytes(reader: &mut &[u8]) -> Result<Self, String> {
+        Ok(match read_u8(reader)? {
+            0 => HeuristicStrategy::None,
+            1 => HeuristicStrategy::BandedStatic {
+                band_min_k: read_i32(reader)?,
+                band_max_k: read_i32(reader)?,
+            },
+            2 => HeuristicStrategy::BandedAdaptive {
+                band_min_k: read_i32(reader)?,
+                band_max_k: read_i32(reader)?,
+                score_steps: read_i32(reader)?,
+            },
+            3 => HeuristicStrategy::WFAdaptive {
+                min_wavefront_length: read_i32(reader)?,
+                max_distance_threshold: read_i32(reader)?,
+                score_steps: read_i32(reader)?,
+            },
+            4 => HeuristicStrategy::XDrop {
+                xdrop: read_i32(reader)?,
+                score_steps: read_i32(reader)?,
+            },
+            5 => HeuristicStrategy::ZDrop {
+                zdrop: read_i32(reader)?,
+                score_steps: read_i32(reader)?,
+            },
+            6 => HeuristicStrategy::WFMash {
+                min_wavefront_length: read_i32(reader)?,
+                max_distance_threshold: read_i32(reader)?,
+                score_steps: read_i32(reader)?,
+            },
+            tag => return Err(format!("Invalid heuristic code: {}", tag)),
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum AlignmentScope {
     ComputeScore,
@@ -124,6 +429,15 @@ pub enum AlignmentScope {
 }
 
 impl AlignmentScope {
+    /// Returns the WFA2-lib value of the scope, `None` for `Undefined`.
+    pub(crate) fn to_value(&self) -> Option<wfa::alignment_scope_t> {
+        match self {
+            Self::ComputeScore => Some(wfa::alignment_scope_t_compute_score),
+            Self::Alignment => Some(wfa::alignment_scope_t_compute_alignment),
+            Self::Undefined => None,
+        }
+    }
+
     pub fn from_scope(val: wfa::alignment_scope_t) -> Self {
         match val {
             v if v == wfa::alignment_scope_t_compute_alignment => Self::Alignment,
@@ -179,9 +493,82 @@ impl MemoryMode {
             _ => Self::Undefined,
         }
     }
+
+    /// Raw WFA2-lib value of the mode; `None` for `Undefined`.
+    pub(crate) fn to_value(&self) -> Option<wfa::wavefront_memory_t> {
+        match self {
+            Self::High => Some(wfa::wavefront_memory_t_wavefront_memory_high),
+            Self::Medium => Some(wfa::wavefront_memory_t_wavefront_memory_med),
+            Self::Low => Some(wfa::wavefront_memory_t_wavefront_memory_low),
+            Self::Ultralow => Some(wfa::wavefront_memory_t_wavefront_memory_ultralow),
+            Self::Undefined => None,
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+impl std::str::FromStr for MemoryMode {
+    type Err = String;
+
+    /// Parses `high`, `med`/`medium`, `low` or `ultralow` (case-insensitive).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "high" => Ok(Self::High),
+            "med" | "medium" => Ok(Self::Medium),
+            "low" => Ok(Self::Low),
+            "ultralow" => Ok(Self::Ultralow),
+            _ => Err(format!("Invalid memory mode: {}", s)),
+        }
+    }
+}
+
+const ALLOCATION_FAILED: &str = "WFA2-lib failed to allocate the aligner";
+
+/// Creates a WFA2-lib aligner from `attributes`, panicking instead of returning null.
+unsafe fn new_wfa_aligner(
+    attributes: &mut wfa::wavefront_aligner_attr_t,
+) -> *mut wfa::wavefront_aligner_t {
+    let wf_aligner = unsafe { wfa::wavefront_aligner_new(attributes) };
+    assert!(!wf_aligner.is_null(), "{}", ALLOCATION_FAILED);
+    wf_aligner
+}
+
+/// Environment variable overriding the memory mode of newly created aligners.
+pub const MEMORY_MODE_ENV: &str = "LIB_WFA2_MEMORY_MODE";
+
+/// Memory mode for new aligners: high, unless overridden through `LIB_WFA2_MEMORY_MODE`.
+///
+/// Constructors set it in the attributes the aligner is created from, since ultralow cannot be
+/// switched on afterwards by writing the field (see `AffineWavefronts::set_memory_mode`). The
+/// variable is read once, when the first aligner is created; an unknown value falls back to
+/// high with a single warning on stderr.
+fn default_memory_mode() -> wfa::wavefront_memory_t {
+    static MODE: std::sync::OnceLock<wfa::wavefront_memory_t> = std::sync::OnceLock::new();
+
+    *MODE.get_or_init(|| {
+        let value = std::env::var(MEMORY_MODE_ENV).ok();
+        memory_mode_from_env(value.as_deref()).unwrap_or_else(|e| {
+            eprintln!(
+                "Warning: {} in {}, falling back to high memory mode",
+                e, MEMORY_MODE_ENV
+            );
+            wfa::wavefront_memory_t_wavefront_memory_high
+        })
+    })
+}
+
+/// Parses a value of `MEMORY_MODE_ENV`, high if it is unset.
+fn memory_mode_from_env(value: Option<&str>) -> Result<wfa::wavefront_memory_t, String> {
+    let Some(value) = value else {
+        return Ok(wfa::wavefront_memory_t_wavefront_memory_high);
+    };
+    let mode = value.parse::<MemoryMode>()?;
+    Ok(mode
+        .to_value()
+        .unwrap_or(wfa::wavefront_memory_t_wavefront_memory_high))
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AlignmentStatus {
     Completed,
     Partial,
@@ -189,6 +576,12 @@ pub enum AlignmentStatus {
     OOM,
     Unattainable,
     Undefined,
+    /// The alignment completed but is shorter than `set_min_alignment_length` allows.
+    TooShort,
+    /// A sequence is longer than WFA2-lib can index (`i32::MAX` bases); nothing was aligned.
+    TooLong,
+    /// The CIGAR could exceed `set_max_cigar_len`; nothing was aligned.
+    CigarTooLong,
 }
 
 impl From<std::os::raw::c_int> for AlignmentStatus {
@@ -204,438 +597,7315 @@ impl From<std::os::raw::c_int> for AlignmentStatus {
     }
 }
 
-pub struct AffineWavefronts {
-    wf_aligner: *mut wfa::wavefront_aligner_t,
+impl AlignmentStatus {
+    /// Whether the alignment finished normally.
+    pub fn is_completed(&self) -> bool {
+        *self == AlignmentStatus::Completed
+    }
+
+    /// Whether the alignment produced a usable result: `Completed`, or `Partial` (e.g. an
+    /// extension cut by a drop heuristic, whose CIGAR covers the aligned part).
+    pub fn is_ok(&self) -> bool {
+        matches!(self, AlignmentStatus::Completed | AlignmentStatus::Partial)
+    }
 }
 
-impl Clone for AffineWavefronts {
-    fn clone(&self) -> Self {
-        Self {
-            wf_aligner: self.wf_aligner,
+/// A single CIGAR operation, as encoded by WFA2-lib.
+///
+/// WFA2-lib describes how to turn the pattern into the text: `Insertion` (`I`) consumes only
+/// the text and `Deletion` (`D`) only the pattern. With the pattern as query and the text as
+/// reference this is the opposite of the SAM convention.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CigarOp {
+    Match,
+    Mismatch,
+    Insertion,
+    Deletion,
+}
+
+impl CigarOp {
+    /// Decodes a WFA2-lib CIGAR byte (`M`, `X`, `I` or `D`).
+    pub fn from_byte(op: u8) -> Option<Self> {
+        match op {
+            b'M' => Some(CigarOp::Match),
+            b'X' => Some(CigarOp::Mismatch),
+            b'I' => Some(CigarOp::Insertion),
+            b'D' => Some(CigarOp::Deletion),
+            _ => None,
         }
     }
-}
 
-impl Default for AffineWavefronts {
-    fn default() -> Self {
-        Self {
-            // null pointer means wavefront_aligner_new will use default attributes.
-            wf_aligner: unsafe { wfa::wavefront_aligner_new(core::ptr::null_mut()) },
+    /// Encodes the operation as a WFA2-lib CIGAR byte.
+    pub fn to_byte(self) -> u8 {
+        match self {
+            CigarOp::Match => b'M',
+            CigarOp::Mismatch => b'X',
+            CigarOp::Insertion => b'I',
+            CigarOp::Deletion => b'D',
         }
     }
 }
 
-impl Drop for AffineWavefronts {
-    fn drop(&mut self) {
-        unsafe {
-            wfa::wavefront_aligner_delete(self.wf_aligner);
+/// Order in which `I` and `D` operations are emitted where they are adjacent.
+///
+/// Reordering a stretch of consecutive gaps does not change which bases are aligned to each
+/// other, and grouping them into one insertion and one deletion never costs more, so any
+/// optimal alignment stays optimal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GapPreference {
+    InsertionFirst,
+    DeletionFirst,
+}
+
+/// Iterator over the runs of identical operations of a CIGAR, as `(op, length)` pairs.
+///
+/// Runs can be consumed from both ends, e.g. to inspect terminal gaps without collecting.
+#[derive(Debug, Clone)]
+pub struct CigarOps<'a> {
+    cigar: &'a [u8],
+}
+
+impl<'a> CigarOps<'a> {
+    pub fn new(cigar: &'a [u8]) -> Self {
+        Self { cigar }
+    }
+}
+
+impl Iterator for CigarOps<'_> {
+    type Item = (CigarOp, u32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(&first) = self.cigar.first() {
+            let length = self.cigar.iter().take_while(|&&op| op == first).count();
+            self.cigar = &self.cigar[length..];
+            if let Some(op) = CigarOp::from_byte(first) {
+                return Some((op, length as u32));
+            }
         }
+        None
     }
 }
 
-impl AffineWavefronts {
-    pub fn aligner_mut(&mut self) -> *mut wfa::wavefront_aligner_t {
-        self.wf_aligner
+impl DoubleEndedIterator for CigarOps<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        while let Some(&last) = self.cigar.last() {
+            let length = self
+                .cigar
+                .iter()
+                .rev()
+                .take_while(|&&op| op == last)
+                .count();
+            self.cigar = &self.cigar[..self.cigar.len() - length];
+            if let Some(op) = CigarOp::from_byte(last) {
+                return Some((op, length as u32));
+            }
+        }
+        None
     }
+}
 
-    pub fn aligner(&self) -> *const wfa::wavefront_aligner_t {
-        self.wf_aligner
+impl std::iter::FusedIterator for CigarOps<'_> {}
+
+/// Owned outcome of an alignment that does not borrow from the aligner.
+#[derive(Debug, Clone)]
+pub struct AlignmentResult {
+    pub status: AlignmentStatus,
+    pub score: i32,
+    pub cigar: Vec<u8>,
+}
+
+impl AlignmentResult {
+    /// Compares this result against `other` (e.g. a stored golden alignment), returning `None`
+    /// when status, score and CIGAR are all identical.
+    pub fn diff(&self, other: &AlignmentResult) -> Option<ResultDiff> {
+        let status =
+            (self.status != other.status).then(|| (self.status.clone(), other.status.clone()));
+        let score = (self.score != other.score).then_some((self.score, other.score));
+        let cigar = (self.cigar != other.cigar).then(|| {
+            let column = self
+                .cigar
+                .iter()
+                .zip(&other.cigar)
+                .take_while(|(a, b)| a == b)
+                .count();
+            CigarDiff {
+                column,
+                left: run_at(&self.cigar, column),
+                right: run_at(&other.cigar, column),
+            }
+        });
+
+        if status.is_none() && score.is_none() && cigar.is_none() {
+            None
+        } else {
+            Some(ResultDiff {
+                status,
+                score,
+                cigar,
+            })
+        }
     }
+}
 
-    fn new_aligner_edit(heuristic: Option<&HeuristicStrategy>) -> Self {
-        unsafe {
-            // Create attributes and set defaults (see https://github.com/smarco/WFA2-lib/blob/2ec2891/wavefront/wavefront_attributes.c#L38)
-            let mut attributes = wfa::wavefront_aligner_attr_default;
+/// Successful alignment returned by `AffineWavefronts::align_checked`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alignment {
+    pub score: i32,
+    pub cigar: Vec<u8>,
+}
 
-            // Set distance mode (includes distance metric and penalties)
-            Self::set_distance_attr(&mut attributes, &Distance::Edit);
+/// Why `AffineWavefronts::align_checked` did not return an alignment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AlignmentError {
+    /// The alignment ended with a status other than `Completed`.
+    Status(AlignmentStatus),
+    /// The combined sequence length exceeds `max_representable_length`.
+    ScoreOverflow { length: usize, max: usize },
+}
 
-            // Set memory mode
-            attributes.memory_mode = wfa::wavefront_memory_t_wavefront_memory_high; // wavefront_memory_t_wavefront_memory_ultralow does not work properly!
+impl std::fmt::Display for AlignmentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlignmentError::Status(status) => write!(f, "alignment did not complete: {:?}", status),
+            AlignmentError::ScoreOverflow { length, max } => write!(
+                f,
+                "{} combined bases could overflow the alignment score (limit {})",
+                length, max
+            ),
+        }
+    }
+}
 
-            // Configure heuristic before creating aligner
-            Self::set_heuristic_attr(&mut attributes, heuristic);
+impl std::error::Error for AlignmentError {}
 
-            // Create aligner with attributes
-            let wf_aligner = wfa::wavefront_aligner_new(&mut attributes);
+/// Why `AffineWavefronts::try_new_edit` could not provide a working aligner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InitError {
+    /// WFA2-lib failed to allocate the aligner.
+    Allocation,
+    /// The self-check alignment ended with a status other than `Completed`.
+    SelfCheckIncomplete(AlignmentStatus),
+    /// The self-check alignment completed with the wrong score or CIGAR.
+    SelfCheckMismatch { score: i32, cigar: Vec<u8> },
+}
 
-            Self { wf_aligner }
+impl std::fmt::Display for InitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InitError::Allocation => write!(f, "{}", ALLOCATION_FAILED),
+            InitError::SelfCheckIncomplete(status) => {
+                write!(f, "WFA2-lib self-check did not complete: {:?}", status)
+            }
+            InitError::SelfCheckMismatch { score, cigar } => write!(
+                f,
+                "WFA2-lib self-check gave score {} and CIGAR {} (expected score 1)",
+                score,
+                String::from_utf8_lossy(cigar)
+            ),
         }
     }
+}
 
-    fn new_aligner_gap_affine(
-        mismatch: i32,
-        gap_opening: i32,
-        gap_extension: i32,
-        heuristic: Option<&HeuristicStrategy>,
-    ) -> Self {
-        unsafe {
-            // Create attributes and set defaults
-            let mut attributes = wfa::wavefront_aligner_attr_default;
+impl std::error::Error for InitError {}
 
-            // Set distance mode (includes distance metric and penalties)
-            Self::set_distance_attr(
-                &mut attributes,
-                &Distance::GapAffine {
-                    mismatch,
-                    gap_opening,
-                    gap_extension,
-                },
-            );
+/// Why `AffineWavefronts::verify_cigar` rejected the CIGAR; `column` is the offending CIGAR column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CigarVerifyError {
+    /// An `M` column pairs different bases.
+    NotAMatch { column: usize },
+    /// An `X` column pairs equal bases.
+    NotAMismatch { column: usize },
+    /// The column needs a pattern base past the end of the pattern.
+    PatternOverrun { column: usize },
+    /// The column needs a text base past the end of the text.
+    TextOverrun { column: usize },
+    /// The column holds something other than `M`, `X`, `I` or `D`.
+    InvalidOperation { column: usize, operation: u8 },
+    /// The CIGAR ends before consuming both sequences.
+    Unconsumed {
+        pattern_left: usize,
+        text_left: usize,
+    },
+}
 
-            // Set memory mode
-            attributes.memory_mode = wfa::wavefront_memory_t_wavefront_memory_high; // wavefront_memory_t_wavefront_memory_ultralow does not work properly!
+impl std::fmt::Display for CigarVerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CigarVerifyError::NotAMatch { column } => {
+                write!(f, "column {} is a match of different bases", column)
+            }
+            CigarVerifyError::NotAMismatch { column } => {
+                write!(f, "column {} is a mismatch of equal bases", column)
+            }
+            CigarVerifyError::PatternOverrun { column } => {
+                write!(f, "column {} runs past the end of the pattern", column)
+            }
+            CigarVerifyError::TextOverrun { column } => {
+                write!(f, "column {} runs past the end of the text", column)
+            }
+            CigarVerifyError::InvalidOperation { column, operation } => write!(
+                f,
+                "column {} has invalid operation {:?}",
+                column, *operation as char
+            ),
+            CigarVerifyError::Unconsumed {
+                pattern_left,
+                text_left,
+            } => write!(
+                f,
+                "CIGAR leaves {} pattern and {} text bases unaligned",
+                pattern_left, text_left
+            ),
+        }
+    }
+}
 
-            // Configure heuristic before creating aligner
-            Self::set_heuristic_attr(&mut attributes, heuristic);
+impl std::error::Error for CigarVerifyError {}
 
-            // Create aligner with attributes
-            let wf_aligner = wfa::wavefront_aligner_new(&mut attributes);
+/// A sequence over any alphabet (nucleotides, amino acids, tokenised text), checked to be safe
+/// to hand to WFA2-lib.
+///
+/// WFA2-lib compares raw bytes and needs no particular encoding, so protein or other sequences
+/// are aligned as they are. It pads the pattern with `!` and the text with `?` to find their
+/// ends without bound checks, so these two bytes are reserved, leaving
+/// `Sequence::MAX_ALPHABET_SIZE` symbols. UTF-8 text is compared byte by byte: a multi-byte
+/// character spans several alignment columns, so map it to single-byte tokens first if each
+/// character should count once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sequence<'a>(&'a [u8]);
 
-            Self { wf_aligner }
+impl<'a> Sequence<'a> {
+    /// Number of distinct byte values a sequence may use.
+    pub const MAX_ALPHABET_SIZE: usize = 254;
+
+    /// Checks that `bytes` avoids WFA2-lib's reserved padding bytes.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, String> {
+        match bytes.iter().position(|&b| b == b'!' || b == b'?') {
+            Some(i) => Err(format!(
+                "Byte {:?} at position {} is reserved by WFA2-lib",
+                bytes[i] as char, i
+            )),
+            None => Ok(Sequence(bytes)),
         }
     }
 
-    fn new_aligner_gap_affine2p(
-        mismatch: i32,
-        gap_opening1: i32,
-        gap_extension1: i32,
-        gap_opening2: i32,
-        gap_extension2: i32,
-        heuristic: Option<&HeuristicStrategy>,
-    ) -> Self {
-        unsafe {
-            // Create attributes and set defaults (see https://github.com/smarco/WFA2-lib/blob/2ec2891/wavefront/wavefront_attributes.c#L38)
-            let mut attributes = wfa::wavefront_aligner_attr_default;
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+}
 
-            // Set distance mode (includes distance metric and penalties)
-            Self::set_distance_attr(
-                &mut attributes,
-                &Distance::GapAffine2p {
-                    mismatch,
-                    gap_opening1,
-                    gap_extension1,
-                    gap_opening2,
-                    gap_extension2,
-                },
-            );
+/// Differences between two alignment results, as `(self, other)` pairs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResultDiff {
+    pub status: Option<(AlignmentStatus, AlignmentStatus)>,
+    pub score: Option<(i32, i32)>,
+    pub cigar: Option<CigarDiff>,
+}
 
-            // Set memory mode
-            attributes.memory_mode = wfa::wavefront_memory_t_wavefront_memory_high; // wavefront_memory_t_wavefront_memory_ultralow does not work properly!
+/// First point where two CIGARs disagree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CigarDiff {
+    /// Index of the first differing CIGAR column.
+    pub column: usize,
+    /// Run covering that column in each CIGAR, `None` if the CIGAR ends before it.
+    pub left: Option<(CigarOp, u32)>,
+    pub right: Option<(CigarOp, u32)>,
+}
 
-            // Configure heuristic before creating aligner
-            Self::set_heuristic_attr(&mut attributes, heuristic);
+impl std::fmt::Display for ResultDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let run = |run: &Option<(CigarOp, u32)>| match run {
+            Some((op, length)) => format!("{}{}", length, op.to_byte() as char),
+            None => "end".to_string(),
+        };
 
-            // Create aligner with attributes
-            let wf_aligner = wfa::wavefront_aligner_new(&mut attributes);
+        let mut parts = Vec::new();
+        if let Some((left, right)) = &self.status {
+            parts.push(format!("status {:?} != {:?}", left, right));
+        }
+        if let Some((left, right)) = self.score {
+            parts.push(format!("score {} != {}", left, right));
+        }
+        if let Some(cigar) = &self.cigar {
+            parts.push(format!(
+                "CIGAR differs at column {} ({} vs {})",
+                cigar.column,
+                run(&cigar.left),
+                run(&cigar.right)
+            ));
+        }
+        write!(f, "{}", parts.join("; "))
+    }
+}
+
+/// Alignment of a read whose terminal bases were soft-clipped before aligning.
+#[derive(Debug, Clone)]
+pub struct ClippedAlignment {
+    /// Alignment of the unclipped part of the read.
+    pub result: AlignmentResult,
+    /// Leading read bases left out of the alignment.
+    pub clip_start: usize,
+    /// Trailing read bases left out of the alignment.
+    pub clip_end: usize,
+}
+
+impl ClippedAlignment {
+    /// Returns the half-open range of the original read covered by the alignment.
+    pub fn query_range(&self) -> std::ops::Range<usize> {
+        let aligned = cigar_spans(&self.result.cigar).0;
+        self.clip_start..self.clip_start + aligned
+    }
+}
+
+/// Alignment of a query against a circular reference.
+#[derive(Debug, Clone)]
+pub struct CircularAlignment {
+    /// Alignment restricted to the aligned part of the reference.
+    pub result: AlignmentResult,
+    /// Reference position the alignment starts at, in `0..reference.len()`.
+    pub reference_start: usize,
+    /// Reference position the alignment ends at (exclusive). Smaller than or equal to
+    /// `reference_start` when the alignment wraps around the origin.
+    pub reference_end: usize,
+    /// Whether the alignment crosses the end of the reference back to its start.
+    pub spans_origin: bool,
+}
+
+/// Suffix-prefix overlap between two reads, as found by `overlap_align`.
+#[derive(Debug, Clone)]
+pub struct Overlap {
+    /// Overlapping part of the first read (a suffix, up to the allowed overhang).
+    pub a_range: std::ops::Range<usize>,
+    /// Overlapping part of the second read (a prefix, up to the allowed overhang).
+    pub b_range: std::ops::Range<usize>,
+    pub score: i32,
+    /// Alignment of `a_range` against `b_range`.
+    pub cigar: Vec<u8>,
+}
 
-            Self { wf_aligner }
+impl Overlap {
+    /// Formats the overlap as a GFA 1 link (`L`) line between `seg_a` (the segment of the
+    /// first read) and `seg_b`, without a trailing newline.
+    ///
+    /// The overlap CIGAR is run-length encoded with `seg_a` as the reference, as in SAM:
+    /// matches and mismatches become `M`, bases only in `seg_b` `I` and bases only in `seg_a`
+    /// `D`. GFA expects the overlap to cover the end of `seg_a` and the start of `seg_b`, so
+    /// this is only exact when the alignment left no overhang (`max_overhang` of 0, or none
+    /// used).
+    pub fn to_gfa_overlap(
+        &self,
+        seg_a: &str,
+        seg_b: &str,
+        orient_a: Orientation,
+        orient_b: Orientation,
+    ) -> String {
+        format!(
+            "L\t{}\t{}\t{}\t{}\t{}",
+            seg_a,
+            orient_a,
+            seg_b,
+            orient_b,
+            sam_cigar(&self.cigar, true, false)
+        )
+    }
+}
+
+/// Strand a sequence is used on, e.g. in a GFA link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    Forward,
+    Reverse,
+}
+
+impl std::fmt::Display for Orientation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Orientation::Forward => write!(f, "+"),
+            Orientation::Reverse => write!(f, "-"),
         }
     }
+}
 
-    /// Align two sequences and return the alignment status.
-    pub fn align(&self, a: &[u8], b: &[u8]) -> AlignmentStatus {
-        unsafe {
-            let a = slice::from_raw_parts(a.as_ptr() as *const i8, a.len());
-            let b = slice::from_raw_parts(b.as_ptr() as *const i8, b.len());
+/// Strand a query aligns on, as reported by `AffineWavefronts::align_both_strands`.
+pub type Strand = Orientation;
 
-            let alignment_status: AlignmentStatus = wfa::wavefront_align(
-                self.wf_aligner,
-                a.as_ptr(),
-                a.len() as i32,
-                b.as_ptr(),
-                b.len() as i32,
-            )
-            .into();
+/// Contiguous piece of an alignment with its pattern (query) and text (reference) coordinates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlignedBlock {
+    pub query_range: std::ops::Range<usize>,
+    pub reference_range: std::ops::Range<usize>,
+    pub cigar: Vec<u8>,
+}
+
+impl AlignedBlock {
+    /// Returns the `(query, reference)` bases skipped between this block and `next`, i.e. the
+    /// size of the breakpoint separating them. Assumes `next` comes after this block.
+    pub fn gap_to(&self, next: &AlignedBlock) -> (usize, usize) {
+        (
+            next.query_range.start.saturating_sub(self.query_range.end),
+            next.reference_range
+                .start
+                .saturating_sub(self.reference_range.end),
+        )
+    }
+}
 
-            alignment_status
+/// Operation counts of an alignment, in bases.
+///
+/// `insertions` counts text-only (`I`) bases and `deletions` pattern-only (`D`) bases, following
+/// WFA2-lib's CIGAR convention.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AlignmentStats {
+    pub matches: usize,
+    pub mismatches: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+    /// Number of insertion (`I`) runs, each counted once whatever its length.
+    pub insertion_events: usize,
+    /// Number of deletion (`D`) runs, each counted once whatever its length.
+    pub deletion_events: usize,
+}
+
+impl AlignmentStats {
+    /// Counts the operations of a CIGAR.
+    pub fn from_cigar(cigar: &[u8]) -> Self {
+        let mut stats = Self::default();
+        for run in cigar.chunk_by(|a, b| a == b) {
+            match run[0] {
+                b'M' => stats.matches += run.len(),
+                b'X' => stats.mismatches += run.len(),
+                b'I' => {
+                    stats.insertions += run.len();
+                    stats.insertion_events += 1;
+                }
+                b'D' => {
+                    stats.deletions += run.len();
+                    stats.deletion_events += 1;
+                }
+                _ => {}
+            }
         }
+        stats
     }
 
-    /// Returns the CIGAR string from the last alignment.
-    pub fn cigar(&self) -> &[u8] {
-        unsafe {
-            let cigar = (*self.wf_aligner).cigar;
-            let ops = (*cigar).operations;
-            let begin_offset = (*cigar).begin_offset;
-            let end_offset = (*cigar).end_offset;
-            let length = end_offset - begin_offset;
+    /// Number of alignment columns.
+    pub fn alignment_length(&self) -> usize {
+        self.matches + self.mismatches + self.insertions + self.deletions
+    }
 
-            let cigar_slice: &[u8] = std::slice::from_raw_parts(
-                (ops as *const u8).add(begin_offset as usize),
-                length.try_into().unwrap(),
-            );
-            cigar_slice
+    /// Matches over alignment columns (BLAST identity); 0 for an empty alignment.
+    pub fn block_identity(&self) -> f64 {
+        match self.alignment_length() {
+            0 => 0.0,
+            length => self.matches as f64 / length as f64,
         }
     }
 
-    /// Returns the alignment score from the last alignment.
-    pub fn score(&self) -> i32 {
-        unsafe {
-            let cigar = (*self.wf_aligner).cigar;
-            (*cigar).score
+    /// Identity counting each gap as a single difference whatever its length, as minimap2 and
+    /// PAF tools do: `matches / (matches + mismatches + insertion_events + deletion_events)`;
+    /// 0 for an empty alignment.
+    pub fn gap_compressed_identity(&self) -> f64 {
+        match self.matches + self.mismatches + self.insertion_events + self.deletion_events {
+            0 => 0.0,
+            columns => self.matches as f64 / columns as f64,
         }
     }
 
-    /// Reclaims any extra buffers the underlying WFA aligner grew during the last run.
-    pub fn clear(&mut self) {
-        unsafe {
-            wfa::wavefront_aligner_reap(self.wf_aligner);
+    /// Edit operations over alignment columns, `(mismatches + insertions + deletions) /
+    /// alignment_length`, in `[0, 1]`; 0 for an empty alignment.
+    pub fn normalized_distance(&self) -> f64 {
+        match self.alignment_length() {
+            0 => 0.0,
+            length => (length - self.matches) as f64 / length as f64,
         }
     }
+}
 
-    /// Report the size of the underlying WFA aligner in bytes.
-    pub fn get_aligner_size(&self) -> u64 {
-        unsafe { wfa::wavefront_aligner_get_size(self.wf_aligner) }
+/// Summary of an alignment, as emitted by `AffineWavefronts::stats_json`.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlignmentSummary {
+    pub status: AlignmentStatus,
+    pub score: i32,
+    pub matches: usize,
+    pub mismatches: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+    pub query_span: usize,
+    pub reference_span: usize,
+    pub block_identity: f64,
+}
+
+/// Cluster of nearby differences of an alignment with its flanking matches, as found by
+/// `AffineWavefronts::compact_diff`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffRegion {
+    pub query_range: std::ops::Range<usize>,
+    pub reference_range: std::ops::Range<usize>,
+    /// CIGAR of the region, flanks included.
+    pub cigar: Vec<u8>,
+    /// Query (pattern) bases of the region.
+    pub query: Vec<u8>,
+    /// Reference (text) bases of the region.
+    pub reference: Vec<u8>,
+}
+
+/// Which of several equally scoring local alignments `AffineWavefronts::local_alignment`
+/// reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocalTiePolicy {
+    /// The one with the most columns.
+    Longest,
+    /// The one starting first (then ending first).
+    Leftmost,
+    /// The one with the highest fraction of matching columns.
+    HighestIdentity,
+}
+
+/// Best-scoring local part of an alignment, as found by `AffineWavefronts::local_alignment`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalAlignment {
+    /// CIGAR columns of the full alignment covered by the local one.
+    pub columns: std::ops::Range<usize>,
+    pub query_range: std::ops::Range<usize>,
+    pub reference_range: std::ops::Range<usize>,
+    /// `match_bonus * matches - penalty` of the covered columns.
+    pub score: i32,
+    pub cigar: Vec<u8>,
+}
+
+/// Indel that changes the length of a homopolymer run, as found by
+/// `AffineWavefronts::homopolymer_indels`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HomopolymerEvent {
+    /// Base the homopolymer is made of.
+    pub base: u8,
+    /// Start of the run in the query (pattern).
+    pub query_pos: usize,
+    /// Start of the run in the reference (text).
+    pub reference_pos: usize,
+    /// Length of the run in the query.
+    pub query_length: usize,
+    /// Length of the run in the reference.
+    pub reference_length: usize,
+}
+
+/// A single edit operation turning the pattern into the text, carrying the bytes involved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditOp {
+    /// Byte present in both sequences.
+    Keep(u8),
+    /// Pattern byte (`old`) replaced by a text byte (`new`).
+    Replace(u8, u8),
+    /// Text byte missing from the pattern.
+    Insert(u8),
+    /// Pattern byte missing from the text.
+    Delete(u8),
+}
+
+/// Genetic code used to translate nucleotide sequences into proteins.
+#[derive(Debug, Clone)]
+pub struct CodonTable {
+    amino_acids: [u8; 64],
+}
+
+impl CodonTable {
+    /// Builds a table from the 64 amino acids of an NCBI translation table, listed in NCBI's
+    /// order (`TTT`, `TTC`, `TTA`, `TTG`, `TCT`, ..., `GGG`), with `*` for stop codons.
+    pub fn from_ncbi(amino_acids: &[u8; 64]) -> Self {
+        Self {
+            amino_acids: *amino_acids,
+        }
     }
 
-    fn set_distance_attr(attributes: &mut wfa::wavefront_aligner_attr_t, mode: &Distance) {
-        match mode {
-            Distance::Edit => {
-                attributes.distance_metric = wfa::distance_metric_t_edit;
-            }
-            Distance::GapAffine {
-                mismatch,
-                gap_opening,
-                gap_extension,
-            } => {
-                attributes.distance_metric = wfa::distance_metric_t_gap_affine;
-                attributes.affine_penalties.mismatch = *mismatch;
-                attributes.affine_penalties.gap_opening = *gap_opening;
-                attributes.affine_penalties.gap_extension = *gap_extension;
-            }
-            Distance::GapAffine2p {
-                mismatch,
-                gap_opening1,
-                gap_extension1,
-                gap_opening2,
-                gap_extension2,
-            } => {
-                attributes.distance_metric = wfa::distance_metric_t_gap_affine_2p;
-                attributes.affine2p_penalties.mismatch = *mismatch;
-                attributes.affine2p_penalties.gap_opening1 = *gap_opening1;
-                attributes.affine2p_penalties.gap_extension1 = *gap_extension1;
-                attributes.affine2p_penalties.gap_opening2 = *gap_opening2;
-                attributes.affine2p_penalties.gap_extension2 = *gap_extension2;
-            }
+    /// The standard genetic code (NCBI table 1).
+    pub fn standard() -> Self {
+        Self::from_ncbi(b"FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG")
+    }
+
+    /// Translates a codon (case-insensitive), giving `X` if it contains other bases than `ACGT`.
+    pub fn translate_codon(&self, codon: &[u8; 3]) -> u8 {
+        let mut index = 0;
+        for base in codon {
+            let code = match base.to_ascii_uppercase() {
+                b'T' => 0,
+                b'C' => 1,
+                b'A' => 2,
+                b'G' => 3,
+                _ => return b'X',
+            };
+            index = index * 4 + code;
         }
+        self.amino_acids[index]
     }
 
-    pub fn get_distance(&self) -> Distance {
-        unsafe {
-            let aligner = *self.aligner();
-            let metric = aligner.penalties.distance_metric;
+    /// Translates `seq` from its first base, dropping a trailing partial codon.
+    pub fn translate(&self, seq: &[u8]) -> Vec<u8> {
+        seq.chunks_exact(3)
+            .map(|codon| self.translate_codon(&[codon[0], codon[1], codon[2]]))
+            .collect()
+    }
+}
 
-            match metric {
-                wfa::distance_metric_t_edit => Distance::Edit,
-                wfa::distance_metric_t_gap_affine => Distance::GapAffine {
-                    mismatch: aligner.penalties.mismatch,
-                    gap_opening: aligner.penalties.gap_opening1,
-                    gap_extension: aligner.penalties.gap_extension1,
-                },
-                wfa::distance_metric_t_gap_affine_2p => Distance::GapAffine2p {
-                    mismatch: aligner.penalties.mismatch,
-                    gap_opening1: aligner.penalties.gap_opening1,
-                    gap_extension1: aligner.penalties.gap_extension1,
-                    gap_opening2: aligner.penalties.gap_opening2,
-                    gap_extension2: aligner.penalties.gap_extension2,
-                },
-                _ => Distance::Edit, // Default fallback
-            }
+/// Best-scoring translation of a nucleotide query against a protein, as found by
+/// `AffineWavefronts::translated_align`.
+#[derive(Debug, Clone)]
+pub struct TranslatedAlignment {
+    /// Reading frame: `1` to `3` start at query offset 0 to 2 on the forward strand, `-1` to
+    /// `-3` at the same offsets on the reverse complement.
+    pub frame: i8,
+    /// Translated query of that frame, as aligned.
+    pub translation: Vec<u8>,
+    /// Alignment of `translation` (pattern) against the protein (text).
+    pub result: AlignmentResult,
+}
+
+/// Placement of one read of a pair on the reference, as found by `AffineWavefronts::align_pair`.
+#[derive(Debug, Clone)]
+pub struct PlacedRead {
+    /// Alignment of the read, or of its reverse complement on the reverse strand, against the
+    /// whole reference with free reference ends.
+    pub result: AlignmentResult,
+    pub orientation: Orientation,
+    /// Reference interval covered by the read.
+    pub reference_range: std::ops::Range<usize>,
+}
+
+/// Alignment of a read pair against a reference.
+#[derive(Debug, Clone)]
+pub struct PairedAlignment {
+    pub read1: PlacedRead,
+    pub read2: PlacedRead,
+    /// Distance from the leftmost start to the rightmost end of the two reads.
+    pub insert_size: usize,
+    /// Whether the reads face each other (forward read first, FR orientation) and the insert
+    /// size is in the expected range.
+    pub concordant: bool,
+}
+
+/// SAM `FLAG` strand bit, `CIGAR` and `SEQ` of a read aligned to the forward reference.
+///
+/// SAM always describes alignments along the forward reference: a read mapped to the reverse
+/// strand is stored reverse-complemented in `SEQ`, with the `0x10` flag bit set, and its CIGAR
+/// is the alignment of that reverse complement. Aligning `reverse_complement(read)` as the
+/// pattern against the forward reference gives that CIGAR directly, without reversing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SamStrand {
+    pub reverse: bool,
+    /// SAM CIGAR, with the read as the query.
+    pub cigar: String,
+    /// Read bases in reference-forward orientation.
+    pub seq: Vec<u8>,
+}
+
+impl SamStrand {
+    /// Builds the SAM fields of `read` (as sequenced) from the WFA2-lib `cigar` of its
+    /// alignment against the forward reference: the read itself as the pattern if `reverse` is
+    /// false, or its reverse complement otherwise.
+    pub fn new(read: &[u8], cigar: &[u8], reverse: bool) -> Self {
+        let seq = if reverse {
+            reverse_complement(read)
+        } else {
+            read.to_vec()
+        };
+        Self {
+            reverse,
+            cigar: sam_cigar(cigar, false, false),
+            seq,
         }
     }
 
-    fn set_heuristic_attr(
-        attributes: &mut wfa::wavefront_aligner_attr_t,
-        heuristic: Option<&HeuristicStrategy>,
-    ) {
-        match heuristic {
-            Some(HeuristicStrategy::BandedStatic {
-                band_min_k,
-                band_max_k,
-            }) => {
-                attributes.heuristic.strategy =
-                    wfa::wf_heuristic_strategy_wf_heuristic_banded_static;
-                attributes.heuristic.min_k = *band_min_k;
-                attributes.heuristic.max_k = *band_max_k;
-            }
-            Some(HeuristicStrategy::BandedAdaptive {
-                band_min_k,
-                band_max_k,
-                score_steps,
-            }) => {
-                attributes.heuristic.strategy =
-                    wfa::wf_heuristic_strategy_wf_heuristic_banded_adaptive;
-                attributes.heuristic.min_k = *band_min_k;
-                attributes.heuristic.max_k = *band_max_k;
-                attributes.heuristic.steps_between_cutoffs = *score_steps;
-            }
-            Some(HeuristicStrategy::WFAdaptive {
-                min_wavefront_length,
-                max_distance_threshold,
-                score_steps,
-            }) => {
-                attributes.heuristic.strategy = wfa::wf_heuristic_strategy_wf_heuristic_wfadaptive;
-                attributes.heuristic.min_wavefront_length = *min_wavefront_length;
-                attributes.heuristic.max_distance_threshold = *max_distance_threshold;
-                attributes.heuristic.steps_between_cutoffs = *score_steps;
-            }
-            Some(HeuristicStrategy::XDrop { xdrop, score_steps }) => {
-                attributes.heuristic.strategy = wfa::wf_heuristic_strategy_wf_heuristic_xdrop;
-                attributes.heuristic.xdrop = *xdrop;
-                attributes.heuristic.steps_between_cutoffs = *score_steps;
-            }
-            Some(HeuristicStrategy::ZDrop { zdrop, score_steps }) => {
-                attributes.heuristic.strategy = wfa::wf_heuristic_strategy_wf_heuristic_zdrop;
-                attributes.heuristic.zdrop = *zdrop;
-                attributes.heuristic.steps_between_cutoffs = *score_steps;
-            }
-            Some(HeuristicStrategy::WFMash {
-                min_wavefront_length,
-                max_distance_threshold,
-                score_steps,
-            }) => {
-                attributes.heuristic.strategy = wfa::wf_heuristic_strategy_wf_heuristic_wfmash;
-                attributes.heuristic.min_wavefront_length = *min_wavefront_length;
-                attributes.heuristic.max_distance_threshold = *max_distance_threshold;
-                attributes.heuristic.steps_between_cutoffs = *score_steps;
-            }
-            Some(HeuristicStrategy::None) | _ => {
-                attributes.heuristic.strategy = wfa::wf_heuristic_strategy_wf_heuristic_none;
-            }
+    /// Returns the strand bit to OR into the SAM `FLAG`: `0x10` on the reverse strand, 0 on
+    /// the forward one.
+    pub fn flag(&self) -> u16 {
+        if self.reverse {
+            0x10
+        } else {
+            0
         }
     }
+}
 
-    /// Update heuristic on an already-created aligner
-    pub fn set_heuristic(&mut self, heuristic: Option<&HeuristicStrategy>) {
-        unsafe {
-            match heuristic {
-                Some(HeuristicStrategy::BandedStatic {
-                    band_min_k,
-                    band_max_k,
-                }) => {
-                    wfa::wavefront_aligner_set_heuristic_banded_static(
-                        self.wf_aligner,
-                        *band_min_k,
-                        *band_max_k,
-                    );
-                }
-                Some(HeuristicStrategy::BandedAdaptive {
-                    band_min_k,
-                    band_max_k,
-                    score_steps,
-                }) => {
-                    wfa::wavefront_aligner_set_heuristic_banded_adaptive(
-                        self.wf_aligner,
-                        *band_min_k,
-                        *band_max_k,
-                        *score_steps,
-                    );
-                }
-                Some(HeuristicStrategy::WFAdaptive {
-                    min_wavefront_length,
-                    max_distance_threshold,
-                    score_steps,
-                }) => {
-                    wfa::wavefront_aligner_set_heuristic_wfadaptive(
-                        self.wf_aligner,
-                        *min_wavefront_length,
-                        *max_distance_threshold,
-                        *score_steps,
-                    );
-                }
-                Some(HeuristicStrategy::XDrop { xdrop, score_steps }) => {
-                    wfa::wavefront_aligner_set_heuristic_xdrop(
-                        self.wf_aligner,
-                        *xdrop,
-                        *score_steps,
-                    );
+/// Memory held by an aligner, in bytes, as reported by `AffineWavefronts::memory_breakdown`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryBreakdown {
+    /// Wavefront storage (the wavefront slab), as its allocator reports it.
+    pub wavefronts: u64,
+    /// Backtrace buffer of the medium and low memory modes, as its allocator reports it.
+    pub backtrace: u64,
+    /// Sub-aligners of the ultralow (bidirectional) mode, as WFA2-lib reports them.
+    pub sub_aligners: u64,
+    /// Whatever `get_aligner_size()` counts beyond the components above; 0 unless WFA2-lib
+    /// starts counting more.
+    pub other: u64,
+    /// CIGAR buffers, which `get_aligner_size()` does not count. Computed from their capacity
+    /// (one byte per operation plus a 4-byte run-length slot per operation), so this is the
+    /// only estimate: allocator overhead is not included.
+    pub cigar: u64,
+}
+
+impl MemoryBreakdown {
+    /// Returns the total `get_aligner_size()` reported at the time, i.e. every component
+    /// except `cigar`.
+    pub fn aligner_size(&self) -> u64 {
+        self.wavefronts + self.backtrace + self.sub_aligners + self.other
+    }
+}
+
+/// Full configuration of a live aligner, as returned by `AffineWavefronts::config_snapshot`.
+///
+/// Unlike `AlignerConfig` it lists every active heuristic, as WFA2-lib can combine them (see
+/// `AffineWavefronts::set_heuristics`).
+#[derive(Debug, Clone)]
+pub struct AlignerSnapshot {
+    pub distance: Distance,
+    pub penalties: Penalties,
+    /// Active heuristics, empty when none is set.
+    pub heuristics: Vec<HeuristicStrategy>,
+    pub scope: AlignmentScope,
+    pub span: AlignmentSpan,
+    pub memory_mode: MemoryMode,
+}
+
+/// Work counters of the last alignment, as read by `AffineWavefronts::run_metrics`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunMetrics {
+    /// Score steps computed (see `AffineWavefronts::score_steps_taken`).
+    pub score_steps: u64,
+    /// Steps where every wavefront was null, i.e. no cell reached that score.
+    pub null_steps: u64,
+    /// Whether a drop heuristic (X-drop, Z-drop) cut the alignment.
+    pub dropped: bool,
+    /// Lowest and highest diagonals any wavefront reached.
+    pub diagonal_range: (i32, i32),
+    /// Memory in use when the alignment ended, in bytes, as WFA2-lib reports it.
+    pub memory_used: u64,
+}
+
+/// Parameters of WFA2-lib's wavefront plot (see `AffineWavefronts::enable_plot`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlotParams {
+    /// Resolution of the plot heatmaps, in points per side.
+    pub resolution_points: i32,
+    /// Recursion level of the bidirectional aligner to plot (`-1` for all levels).
+    pub align_level: i32,
+}
+
+/// WFA2-lib's default plot parameters.
+impl Default for PlotParams {
+    fn default() -> Self {
+        let plot = unsafe { wfa::wavefront_aligner_attr_default.plot };
+        Self {
+            resolution_points: plot.resolution_points,
+            align_level: plot.align_level,
+        }
+    }
+}
+
+// The C standard library's stream functions, to hand WFA2-lib a `FILE` to print plots to.
+unsafe extern "C" {
+    fn fopen(
+        path: *const std::os::raw::c_char,
+        mode: *const std::os::raw::c_char,
+    ) -> *mut wfa::FILE;
+    fn fclose(stream: *mut wfa::FILE) -> std::os::raw::c_int;
+}
+
+// Part of WFA2-lib, but missing from the generated bindings.
+unsafe extern "C" {
+    fn wf_backtrace_buffer_get_size_allocated(bt_buffer: *mut wfa::wf_backtrace_buffer_t) -> u64;
+}
+
+/// Reusable description of how to build an aligner.
+#[derive(Debug, Clone)]
+pub struct AlignerConfig {
+    pub distance: Distance,
+    pub heuristic: HeuristicStrategy,
+    pub span: AlignmentSpan,
+    /// Memory mode, or `None` for the default given by `MEMORY_MODE_ENV`.
+    pub memory_mode: Option<MemoryMode>,
+    pub scope: AlignmentScope,
+}
+
+impl AlignerConfig {
+    /// Defaults for short-read mapping (Illumina-like reads of a few hundred bases).
+    ///
+    /// Gap-affine with mismatch 4, gap opening 6 and gap extension 2 (WFA2-lib's own defaults,
+    /// close to BWA-MEM's scoring), the WF-adaptive heuristic with minimum wavefront length 10,
+    /// maximum distance threshold 50 and cutoffs every step, as recommended by WFA2-lib for
+    /// short reads, and end-to-end alignment.
+    pub fn short_read_preset() -> Self {
+        Self {
+            distance: Distance::GapAffine {
+                mismatch: 4,
+                gap_opening: 6,
+                gap_extension: 2,
+            },
+            heuristic: HeuristicStrategy::WFAdaptive {
+                min_wavefront_length: 10,
+                max_distance_threshold: 50,
+                score_steps: 1,
+            },
+            span: AlignmentSpan::End2End,
+            memory_mode: None,
+            scope: AlignmentScope::Alignment,
+        }
+    }
+
+    /// Defaults for long-read alignment (noisy reads of tens of kilobases).
+    ///
+    /// Dual-cost gap-affine with mismatch 4, short gaps 6+2 and long gaps 24+1, so long indels
+    /// are not over-penalized, the WF-adaptive heuristic with minimum wavefront length 10 and a
+    /// more permissive maximum distance threshold of 200 to tolerate indel-rich reads, with
+    /// cutoffs every step, and end-to-end alignment.
+    pub fn long_read_preset() -> Self {
+        Self {
+            distance: Distance::GapAffine2p {
+                mismatch: 4,
+                gap_opening1: 6,
+                gap_extension1: 2,
+                gap_opening2: 24,
+                gap_extension2: 1,
+            },
+            heuristic: HeuristicStrategy::WFAdaptive {
+                min_wavefront_length: 10,
+                max_distance_threshold: 200,
+                score_steps: 1,
+            },
+            span: AlignmentSpan::End2End,
+            memory_mode: None,
+            scope: AlignmentScope::Alignment,
+        }
+    }
+
+    /// Builds an aligner with this configuration.
+    ///
+    /// # Panics
+    ///
+    /// If WFA2-lib fails to allocate the aligner; see `try_create_aligner`.
+    pub fn create_aligner(&self) -> AffineWavefronts {
+        self.builder().build()
+    }
+
+    /// Builds an aligner with this configuration, or returns an error if WFA2-lib fails to
+    /// allocate it.
+    pub fn try_create_aligner(&self) -> Result<AffineWavefronts, String> {
+        self.builder().try_build()
+    }
+
+    /// Builds an aligner with this configuration that only computes scores, without CIGARs.
+    fn create_score_only_aligner(&self) -> AffineWavefronts {
+        self.builder().scope(AlignmentScope::ComputeScore).build()
+    }
+
+    fn builder(&self) -> AffineWavefrontsBuilder {
+        let builder = AffineWavefrontsBuilder::new()
+            .distance(self.distance)
+            .heuristic(self.heuristic.clone())
+            .scope(self.scope.clone())
+            .span(self.span.clone());
+        match &self.memory_mode {
+            Some(memory_mode) => builder.memory_mode(memory_mode.clone()),
+            None => builder,
+        }
+    }
+
+    /// Encodes the configuration, e.g. to persist alignment parameters and rebuild an
+    /// identical aligner later with `from_bytes` and `create_aligner`.
+    ///
+    /// The layout is `Distance::to_bytes`, `HeuristicStrategy::to_bytes`, then one byte each
+    /// for the memory mode (0 for `None`, then high, medium, low, ultralow) and the scope
+    /// (0 score only, 1 alignment, 2 undefined), then the span: 0 for end-to-end, 1 followed by
+    /// the four free-end lengths as little-endian `i32`s for ends-free, 2 for undefined.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.distance.to_bytes();
+        bytes.extend(self.heuristic.to_bytes());
+        bytes.push(match self.memory_mode {
+            None => 0,
+            Some(MemoryMode::High) => 1,
+            Some(MemoryMode::Medium) => 2,
+            Some(MemoryMode::Low) => 3,
+            Some(MemoryMode::Ultralow) => 4,
+            Some(MemoryMode::Undefined) => 5,
+        });
+        bytes.push(match self.scope {
+            AlignmentScope::ComputeScore => 0,
+            AlignmentScope::Alignment => 1,
+            AlignmentScope::Undefined => 2,
+        });
+        match self.span {
+            AlignmentSpan::End2End => bytes.push(0),
+            AlignmentSpan::EndsFree {
+                pattern_begin_free,
+                pattern_end_free,
+                text_begin_free,
+                text_end_free,
+            } => {
+                bytes.push(1);
+                for free in [
+                    pattern_begin_free,
+                    pattern_end_free,
+                    text_begin_free,
+                    text_end_free,
+                ] {
+                    bytes.extend(free.to_le_bytes());
                 }
-                Some(HeuristicStrategy::ZDrop { zdrop, score_steps }) => {
-                    wfa::wavefront_aligner_set_heuristic_zdrop(
-                        self.wf_aligner,
-                        *zdrop,
-                        *score_steps,
-                    );
+            }
+            AlignmentSpan::Undefined => bytes.push(2),
+        }
+        bytes
+    }
+
+    /// Decodes a configuration written by `to_bytes`; trailing bytes are an error.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = bytes;
+        let distance = Distance::read_bytes(&mut reader)?;
+        let heuristic = HeuristicStrategy::read_bytes(&mut reader)?;
+        let memory_mode = match read_u8(&mut reader)? {
+            0 => None,
+            1 => Some(MemoryMode::High),
+            2 => Some(MemoryMode::Medium),
+            3 => Some(MemoryMode::Low),
+            4 => Some(MemoryMode::Ultralow),
+            5 => Some(MemoryMode::Undefined),
+            code => return Err(format!("Invalid memory mode code: {}", code)),
+        };
+        let scope = match read_u8(&mut reader)? {
+            0 => AlignmentScope::ComputeScore,
+            1 => AlignmentScope::Alignment,
+            2 => AlignmentScope::Undefined,
+            code => return Err(format!("Invalid scope code: {}", code)),
+        };
+        let span = match read_u8(&mut reader)? {
+            0 => AlignmentSpan::End2End,
+            1 => AlignmentSpan::EndsFree {
+                pattern_begin_free: read_i32(&mut reader)?,
+                pattern_end_free: read_i32(&mut reader)?,
+                text_begin_free: read_i32(&mut reader)?,
+                text_end_free: read_i32(&mut reader)?,
+            },
+            2 => AlignmentSpan::Undefined,
+            code => return Err(format!("Invalid span code: {}", code)),
+        };
+        expect_consumed(reader)?;
+        Ok(Self {
+            distance,
+            heuristic,
+            span,
+            memory_mode,
+            scope,
+        })
+    }
+}
+
+/// Reads one byte from the front of `reader`, advancing it.
+fn read_u8(reader: &mut &[u8]) -> Result<u8, String> {
+    let (&byte, rest) = reader
+        .split_first()
+        .ok_or_else(|| "Unexpected end of input".to_string())?;
+    *reader = rest;
+    Ok(byte)
+}
+
+/// Reads a little-endian `i32` from the front of `reader`, advancing it.
+fn read_i32(reader: &mut &[u8]) -> Result<i32, String> {
+    let Some((bytes, rest)) = reader.split_first_chunk::<4>() else {
+        return Err("Unexpected end of input".to_string());
+    };
+    *reader = rest;
+    Ok(i32::from_le_bytes(*bytes))
+}
+
+fn expect_consumed(reader: &[u8]) -> Result<(), String> {
+    match reader.len() {
+        0 => Ok(()),
+        left => Err(format!("{} trailing bytes after the encoded value", left)),
+    }
+}
+
+/// Builder assembling all WFA2-lib attributes of an aligner before creating it, so memory
+/// mode, heuristic, scope and span are set in a single `wavefront_aligner_new` call.
+///
+/// Unset options keep the defaults of `AffineWavefronts::default`.
+///
+/// ```
+/// use lib_wfa2::affine_wavefront::{
+///     AffineWavefrontsBuilder, AlignmentScope, AlignmentSpan, Distance, HeuristicStrategy,
+///     MemoryMode,
+/// };
+///
+/// let aligner = AffineWavefrontsBuilder::new()
+///     .distance(Distance::GapAffine {
+///         mismatch: 4,
+///         gap_opening: 6,
+///         gap_extension: 2,
+///     })
+///     .heuristic(HeuristicStrategy::WFAdaptive {
+///         min_wavefront_length: 10,
+///         max_distance_threshold: 50,
+///         score_steps: 1,
+///     })
+///     .memory_mode(MemoryMode::Medium)
+///     .scope(AlignmentScope::Alignment)
+///     .span(AlignmentSpan::EndsFree {
+///         pattern_begin_free: 0,
+///         pattern_end_free: 0,
+///         text_begin_free: 10,
+///         text_end_free: 10,
+///     })
+///     .build();
+/// aligner.align(b"ACGTACGT", b"TTACGTACGTTT");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AffineWavefrontsBuilder {
+    distance: Option<Distance>,
+    heuristic: Option<HeuristicStrategy>,
+    memory_mode: Option<MemoryMode>,
+    scope: Option<AlignmentScope>,
+    span: Option<AlignmentSpan>,
+}
+
+impl AffineWavefrontsBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn distance(mut self, distance: Distance) -> Self {
+        self.distance = Some(distance);
+        self
+    }
+
+    pub fn heuristic(mut self, heuristic: HeuristicStrategy) -> Self {
+        self.heuristic = Some(heuristic);
+        self
+    }
+
+    /// Memory mode; without it, the mode comes from `MEMORY_MODE_ENV` as for other aligners.
+    pub fn memory_mode(mut self, memory_mode: MemoryMode) -> Self {
+        self.memory_mode = Some(memory_mode);
+        self
+    }
+
+    pub fn scope(mut self, scope: AlignmentScope) -> Self {
+        self.scope = Some(scope);
+        self
+    }
+
+    pub fn span(mut self, span: AlignmentSpan) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    /// Creates the aligner. `Undefined` scopes, spans and memory modes keep the defaults.
+    ///
+    /// # Panics
+    ///
+    /// If WFA2-lib fails to allocate the aligner; see `try_build`.
+    pub fn build(self) -> AffineWavefronts {
+        self.try_build().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Creates the aligner, or returns an error if WFA2-lib fails to allocate it.
+    pub fn try_build(self) -> Result<AffineWavefronts, String> {
+        unsafe {
+            let mut attributes = wfa::wavefront_aligner_attr_default;
+            if let Some(distance) = &self.distance {
+                AffineWavefronts::set_distance_attr(&mut attributes, distance);
+            }
+            attributes.memory_mode = self
+                .memory_mode
+                .as_ref()
+                .and_then(MemoryMode::to_value)
+                .unwrap_or_else(default_memory_mode);
+            AffineWavefronts::set_heuristic_attr(&mut attributes, self.heuristic.as_ref());
+            if let Some(scope) = self.scope.as_ref().and_then(AlignmentScope::to_value) {
+                attributes.alignment_scope = scope;
+            }
+            match self.span {
+                Some(AlignmentSpan::End2End) => {
+                    attributes.alignment_form.span = wfa::alignment_span_t_alignment_end2end;
                 }
-                Some(HeuristicStrategy::WFMash {
-                    min_wavefront_length,
-                    max_distance_threshold,
-                    score_steps,
+                Some(AlignmentSpan::EndsFree {
+                    pattern_begin_free,
+                    pattern_end_free,
+                    text_begin_free,
+                    text_end_free,
                 }) => {
-                    wfa::wavefront_aligner_set_heuristic_wfmash(
-                        self.wf_aligner,
-                        *min_wavefront_length,
-                        *max_distance_threshold,
-                        *score_steps,
-                    );
-                }
-                Some(HeuristicStrategy::None) | None => {
-                    wfa::wavefront_aligner_set_heuristic_none(self.wf_aligner);
+                    attributes.alignment_form.span = wfa::alignment_span_t_alignment_endsfree;
+                    attributes.alignment_form.pattern_begin_free = pattern_begin_free;
+                    attributes.alignment_form.pattern_end_free = pattern_end_free;
+                    attributes.alignment_form.text_begin_free = text_begin_free;
+                    attributes.alignment_form.text_end_free = text_end_free;
                 }
+                Some(AlignmentSpan::Undefined) | None => {}
             }
+            AffineWavefronts::try_from_aligner(wfa::wavefront_aligner_new(&mut attributes))
         }
     }
+}
 
-    pub fn get_heuristics(&self) -> Vec<HeuristicStrategy> {
-        let mut hs = Vec::new();
-        let heuristic = unsafe { *self.wf_aligner }.heuristic;
-        let strategy = heuristic.strategy;
+pub struct AffineWavefronts {
+    wf_aligner: *mut wfa::wavefront_aligner_t,
+    identical_fast_path: bool,
+    /// Alignments answered by the identical-sequence shortcut, see `identical_shortcut_count`.
+    identical_shortcuts: std::cell::Cell<u64>,
+    gap_preference: Option<GapPreference>,
+    left_align_indels: bool,
+    max_cigar_len: Option<usize>,
+    min_alignment_length: usize,
+    label: Option<String>,
+    /// Attributes the aligner was created with, restored by `reset`.
+    initial_attributes: wfa::wavefront_aligner_attr_t,
+    /// Score-only copy used by `score_only`, created on first use and dropped by `rebuild`.
+    score_only_copy: std::cell::RefCell<Option<Box<AffineWavefronts>>>,
+}
 
-        if strategy & wfa::wf_heuristic_strategy_wf_heuristic_zdrop > 0 {
-            hs.push(HeuristicStrategy::ZDrop {
-                zdrop: heuristic.zdrop,
-                score_steps: heuristic.steps_between_cutoffs,
-            });
-        }
-        if strategy & wfa::wf_heuristic_strategy_wf_heuristic_xdrop > 0 {
-            hs.push(HeuristicStrategy::XDrop {
-                xdrop: heuristic.zdrop,
-                score_steps: heuristic.steps_between_cutoffs,
-            });
-        }
-        if strategy & wfa::wf_heuristic_strategy_wf_heuristic_banded_adaptive > 0 {
-            hs.push(HeuristicStrategy::BandedAdaptive {
-                band_min_k: heuristic.min_k,
-                band_max_k: heuristic.max_k,
-                score_steps: heuristic.steps_between_cutoffs,
-            });
-        }
-        if strategy & wfa::wf_heuristic_strategy_wf_heuristic_banded_static > 0 {
-            hs.push(HeuristicStrategy::BandedStatic {
-                band_min_k: heuristic.min_k,
-                band_max_k: heuristic.max_k,
-            });
+// SAFETY: the aligner exclusively owns its WFA2-lib state (wavefronts, CIGAR, allocator) and
+// WFA2-lib keeps no global mutable state tied to it, so it can be moved to another thread.
+// It is deliberately not `Sync`: `align` takes `&self` but mutates that state, so one aligner
+// must never be used from several threads at once; give each thread its own aligner.
+unsafe impl Send for AffineWavefronts {}
+
+/// Creates an independent aligner with the same configuration (penalties, heuristic, scope,
+/// span, memory mode, limits and Rust-side settings). The last alignment is not copied.
+///
+/// # Panics
+///
+/// If WFA2-lib fails to allocate the new aligner.
+impl Clone for AffineWavefronts {
+    fn clone(&self) -> Self {
+        self.with_attributes(self.current_attributes())
+    }
+}
+
+impl std::fmt::Debug for AffineWavefronts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AffineWavefronts")
+            .field("label", &self.label)
+            .field("distance", &self.get_distance())
+            .field("heuristics", &self.get_heuristics())
+            .field("scope", &self.get_alignment_scope())
+            .field("span", &self.get_alignment_span())
+            .field("memory_mode", &self.get_memory_mode())
+            .field("gap_preference", &self.gap_preference)
+            .field("left_align_indels", &self.left_align_indels)
+            .field("max_cigar_len", &self.max_cigar_len)
+            .field("min_alignment_length", &self.min_alignment_length)
+            .finish()
+    }
+}
+
+/// Creates a gap-affine aligner with WFA2-lib's default attributes: mismatch 4, gap opening 6,
+/// gap extension 2, no heuristic, end-to-end, full alignment (score and CIGAR), and the memory
+/// mode given by `MEMORY_MODE_ENV` (high if unset).
+///
+/// This is intentionally not an edit-distance aligner, and is equivalent to
+/// `Distance::GapAffine { mismatch: 4, gap_opening: 6, gap_extension: 2 }.create_aligner(None)`.
+/// Use `Distance::Edit.create_aligner(None)` for edit distance.
+///
+/// # Panics
+///
+/// If WFA2-lib fails to allocate the aligner; see `AffineWavefrontsBuilder::try_build`.
+impl Default for AffineWavefronts {
+    fn default() -> Self {
+        unsafe {
+            // Same attributes a null pointer would give, except for the memory mode override.
+            let mut attributes = wfa::wavefront_aligner_attr_default;
+            attributes.memory_mode = default_memory_mode();
+            Self::from_aligner(wfa::wavefront_aligner_new(&mut attributes))
         }
-        if strategy & wfa::wf_heuristic_strategy_wf_heuristic_wfadaptive > 0 {
-            hs.push(HeuristicStrategy::WFAdaptive {
-                min_wavefront_length: heuristic.min_wavefront_length,
-                max_distance_threshold: heuristic.max_distance_threshold,
-                score_steps: heuristic.steps_between_cutoffs,
-            });
+    }
+}
+
+impl Drop for AffineWavefronts {
+    fn drop(&mut self) {
+        // Never null for aligners built by this crate, but `delete` must not be given one.
+        if !self.wf_aligner.is_null() {
+            unsafe {
+                wfa::wavefront_aligner_delete(self.wf_aligner);
+            }
         }
-        if strategy & wfa::wf_heuristic_strategy_wf_heuristic_wfmash > 0 {
-            hs.push(HeuristicStrategy::WFMash {
-                min_wavefront_length: heuristic.min_wavefront_length,
-                max_distance_threshold: heuristic.max_distance_threshold,
-                score_steps: heuristic.steps_between_cutoffs,
-            });
+    }
+}
+
+impl AffineWavefronts {
+    pub fn aligner_mut(&mut self) -> *mut wfa::wavefront_aligner_t {
+        self.wf_aligner
+    }
+
+    pub fn aligner(&self) -> *const wfa::wavefront_aligner_t {
+        self.wf_aligner
+    }
+
+    /// Wraps a freshly created aligner, panicking if WFA2-lib returned null.
+    fn from_aligner(wf_aligner: *mut wfa::wavefront_aligner_t) -> Self {
+        Self::try_from_aligner(wf_aligner).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_from_aligner(wf_aligner: *mut wfa::wavefront_aligner_t) -> Result<Self, String> {
+        if wf_aligner.is_null() {
+            return Err(ALLOCATION_FAILED.to_string());
         }
-        hs
+        let mut aligner = Self {
+            wf_aligner,
+            identical_fast_path: true,
+            identical_shortcuts: std::cell::Cell::new(0),
+            gap_preference: None,
+            left_align_indels: false,
+            max_cigar_len: None,
+            min_alignment_length: 0,
+            label: None,
+            initial_attributes: unsafe { wfa::wavefront_aligner_attr_default },
+            score_only_copy: std::cell::RefCell::new(None),
+        };
+        aligner.initial_attributes = aligner.current_attributes();
+        Ok(aligner)
     }
 
-    pub fn get_alignment_scope(&self) -> AlignmentScope {
-        let a = unsafe { *self.wf_aligner };
-        AlignmentScope::from_scope(a.alignment_scope)
+    fn new_aligner_edit(heuristic: Option<&HeuristicStrategy>) -> Result<Self, String> {
+        unsafe {
+            // Create attributes and set defaults (see https://github.com/smarco/WFA2-lib/blob/2ec2891/wavefront/wavefront_attributes.c#L38)
+            let mut attributes = wfa::wavefront_aligner_attr_default;
+
+            // Set distance mode (includes distance metric and penalties)
+            Self::set_distance_attr(&mut attributes, &Distance::Edit);
+
+            // Set memory mode
+            attributes.memory_mode = default_memory_mode();
+
+            // Configure heuristic before creating aligner
+            Self::set_heuristic_attr(&mut attributes, heuristic);
+
+            // Create aligner with attributes
+            let wf_aligner = wfa::wavefront_aligner_new(&mut attributes);
+
+            Self::try_from_aligner(wf_aligner)
+        }
     }
 
-    pub fn get_alignment_span(&self) -> AlignmentSpan {
-        let form = unsafe { *self.aligner() }.alignment_form;
-        AlignmentSpan::from_form(form)
+    fn new_aligner_gap_linear(
+        mismatch: i32,
+        indel: i32,
+        heuristic: Option<&HeuristicStrategy>,
+    ) -> Result<Self, String> {
+        unsafe {
+            // Create attributes and set defaults
+            let mut attributes = wfa::wavefront_aligner_attr_default;
+
+            // Set distance mode (includes distance metric and penalties)
+            Self::set_distance_attr(&mut attributes, &Distance::GapLinear { mismatch, indel });
+
+            // Set memory mode
+            attributes.memory_mode = default_memory_mode();
+
+            // Configure heuristic before creating aligner
+            Self::set_heuristic_attr(&mut attributes, heuristic);
+
+            // Create aligner with attributes
+            let wf_aligner = wfa::wavefront_aligner_new(&mut attributes);
+
+            Self::try_from_aligner(wf_aligner)
+        }
     }
 
-    pub fn get_memory_mode(&self) -> MemoryMode {
-        let a = unsafe { *self.aligner() };
-        MemoryMode::from_value(a.memory_mode)
+    fn new_aligner_gap_affine(
+        mismatch: i32,
+        gap_opening: i32,
+        gap_extension: i32,
+        heuristic: Option<&HeuristicStrategy>,
+    ) -> Result<Self, String> {
+        unsafe {
+            // Create attributes and set defaults
+            let mut attributes = wfa::wavefront_aligner_attr_default;
+
+            // Set distance mode (includes distance metric and penalties)
+            Self::set_distance_attr(
+                &mut attributes,
+                &Distance::GapAffine {
+                    mismatch,
+                    gap_opening,
+                    gap_extension,
+                },
+            );
+
+            // Set memory mode
+            attributes.memory_mode = default_memory_mode();
+
+            // Configure heuristic before creating aligner
+            Self::set_heuristic_attr(&mut attributes, heuristic);
+
+            // Create aligner with attributes
+            let wf_aligner = wfa::wavefront_aligner_new(&mut attributes);
+
+            Self::try_from_aligner(wf_aligner)
+        }
+    }
+
+    fn new_aligner_gap_affine2p(
+        mismatch: i32,
+        gap_opening1: i32,
+        gap_extension1: i32,
+        gap_opening2: i32,
+        gap_extension2: i32,
+        heuristic: Option<&HeuristicStrategy>,
+    ) -> Result<Self, String> {
+        unsafe {
+            // Create attributes and set defaults (see https://github.com/smarco/WFA2-lib/blob/2ec2891/wavefront/wavefront_attributes.c#L38)
+            let mut attributes = wfa::wavefront_aligner_attr_default;
+
+            // Set distance mode (includes distance metric and penalties)
+            Self::set_distance_attr(
+                &mut attributes,
+                &Distance::GapAffine2p {
+                    mismatch,
+                    gap_opening1,
+                    gap_extension1,
+                    gap_opening2,
+                    gap_extension2,
+                },
+            );
+
+            // Set memory mode
+            attributes.memory_mode = default_memory_mode();
+
+            // Configure heuristic before creating aligner
+            Self::set_heuristic_attr(&mut attributes, heuristic);
+
+            // Create aligner with attributes
+            let wf_aligner = wfa::wavefront_aligner_new(&mut attributes);
+
+            Self::try_from_aligner(wf_aligner)
+        }
+    }
+
+    /// Creates an edit-distance aligner and checks it on a known alignment, so a broken or
+    /// misconfigured WFA2-lib build is reported at startup instead of giving wrong results.
+    pub fn try_new_edit() -> Result<Self, InitError> {
+        let aligner = AffineWavefrontsBuilder::new()
+            .distance(Distance::Edit)
+            .try_build()
+            .map_err(|_| InitError::Allocation)?;
+
+        // One deletion: "ACGT" -> "AGT"
+        let (pattern, text) = (b"ACGT", b"AGT");
+        let status = aligner.align(pattern, text);
+        if status != AlignmentStatus::Completed {
+            return Err(InitError::SelfCheckIncomplete(status));
+        }
+        let spans = cigar_spans(aligner.cigar());
+        if aligner.score() != 1 || spans != (pattern.len(), text.len()) {
+            return Err(InitError::SelfCheckMismatch {
+                score: aligner.score(),
+                cigar: aligner.cigar_owned(),
+            });
+        }
+        Ok(aligner)
+    }
+
+    /// Attaches a free-form label to the aligner, e.g. to trace which configuration produced a
+    /// result when managing many aligners. It is only kept on the Rust side.
+    pub fn set_label(&mut self, label: impl Into<String>) {
+        self.label = Some(label.into());
+    }
+
+    /// Returns the label set with `set_label`, if any.
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// Enables or disables the shortcut for identical sequences (enabled by default).
+    ///
+    /// When enabled, `align` on byte-identical inputs writes an all-match CIGAR with score 0
+    /// straight into the aligner instead of running WFA. The shortcut is skipped if the
+    /// aligner's CIGAR buffer is too small for the sequence, in which case WFA runs as usual.
+    pub fn set_identical_fast_path(&mut self, enabled: bool) {
+        self.identical_fast_path = enabled;
+    }
+
+    /// Returns how many alignments the identical-sequence shortcut answered without running
+    /// WFA, since the aligner was created (clones start from 0).
+    pub fn identical_shortcut_count(&self) -> u64 {
+        self.identical_shortcuts.get()
+    }
+
+    /// Sets how adjacent insertions and deletions are ordered in the CIGAR of every following
+    /// alignment, or `None` (the default) to keep the order WFA2-lib's backtrace produces.
+    ///
+    /// This only decides between `I`-before-`D` and `D`-before-`I` at the same locus, so
+    /// equivalent variants get the same representation; it does not shift gaps along the
+    /// sequences.
+    pub fn set_gap_preference(&mut self, preference: Option<GapPreference>) {
+        self.gap_preference = preference;
+    }
+
+    /// Reorders every stretch of consecutive gaps of the last CIGAR following `preference`.
+    fn apply_gap_preference(&self, preference: GapPreference) {
+        let first = match preference {
+            GapPreference::InsertionFirst => b'I',
+            GapPreference::DeletionFirst => b'D',
+        };
+        let cigar = unsafe { cigar_operations_mut((*self.wf_aligner).cigar) };
+        for gaps in cigar.split_mut(|&op| op != b'I' && op != b'D') {
+            gaps.sort_unstable_by_key(|&op| op != first);
+        }
+    }
+
+    /// Enables or disables left-aligning the indels of every following alignment against the
+    /// text (disabled by default).
+    ///
+    /// Each gap is shifted towards the start of the sequences as long as this keeps the same
+    /// bases matched, so reads carrying the same indel in a repeat all get the same CIGAR
+    /// whatever placement WFA2-lib's backtrace picked, as pileup-based variant calling expects.
+    /// The score does not change. It runs before `set_gap_preference` reordering.
+    pub fn set_left_align_indels(&mut self, enabled: bool) {
+        self.left_align_indels = enabled;
+    }
+
+    /// Caps the CIGAR length of full alignments at `max` operations.
+    ///
+    /// WFA2-lib sizes the CIGAR buffer for the worst case, the sum of both sequence lengths,
+    /// before aligning, whatever the memory mode: the memory modes only change how wavefronts
+    /// are stored. So `align` rejects pairs whose lengths add up to more than `max` up front,
+    /// returning `AlignmentStatus::CigarTooLong` with an empty CIGAR, instead of growing the
+    /// buffer. Score-only aligners build no CIGAR and are never capped.
+    pub fn set_max_cigar_len(&mut self, max: usize) {
+        self.max_cigar_len = Some(max);
+    }
+
+    /// Stops alignments after `steps` score steps, making `align` return
+    /// `AlignmentStatus::MaxStepsReached` instead of running on divergent inputs.
+    pub fn set_max_alignment_steps(&mut self, steps: i32) {
+        unsafe { wfa::wavefront_aligner_set_max_alignment_steps(self.wf_aligner, steps) };
+    }
+
+    /// Aborts alignments whose wavefronts take more than `bytes`, making `align` return
+    /// `AlignmentStatus::OOM` instead of growing without bound.
+    ///
+    /// WFA2-lib first tries to free memory by compacting its backtrace buffer once the resident
+    /// limit is exceeded; that limit is lowered to `bytes` if it was higher, so compaction still
+    /// gets a chance before the abort.
+    pub fn set_max_memory(&mut self, bytes: u64) {
+        unsafe {
+            let resident = (*self.wf_aligner).system.max_memory_resident.min(bytes);
+            wfa::wavefront_aligner_set_max_memory(self.wf_aligner, resident, bytes);
+        }
+    }
+
+    /// Makes `align` report `AlignmentStatus::TooShort` for completed alignments spanning fewer
+    /// than `min` columns (0, the default, accepts everything).
+    ///
+    /// The length counted is that of the aligned block, from the first to the last match or
+    /// mismatch, so terminal gaps (the free ends of an ends-free alignment, or clipped tails)
+    /// do not count and a few matching bases surrounded by free ends are rejected. The CIGAR is
+    /// kept for inspection. Score-only aligners have no CIGAR and are never rejected.
+    pub fn set_min_alignment_length(&mut self, min: usize) {
+        self.min_alignment_length = min;
+    }
+
+    /// Records a rejected alignment: empty CIGAR and a failure (out-of-memory) status.
+    fn write_rejected_alignment(&self) {
+        unsafe {
+            let cigar = (*self.wf_aligner).cigar;
+            (*cigar).begin_offset = 0;
+            (*cigar).end_offset = 0;
+            (*cigar).score = 0;
+            (*cigar).cigar_length = 0;
+            (*self.wf_aligner).align_status.status = -200;
+        }
+    }
+
+    /// Writes the alignment of two identical sequences of `length` bases into the aligner.
+    ///
+    /// Returns `false`, leaving the aligner untouched, if the CIGAR buffer cannot hold it.
+    fn write_identical_alignment(&self, length: usize) -> bool {
+        self.write_uniform_alignment(b'M', length, length, 0)
+    }
+
+    /// Writes a completed alignment made of a single operation repeated (all matches, or one
+    /// gap when a sequence is empty) with the given penalty into the aligner.
+    ///
+    /// Returns `false`, leaving the aligner untouched, if the CIGAR buffer cannot hold it.
+    fn write_uniform_alignment(
+        &self,
+        op: u8,
+        pattern_length: usize,
+        text_length: usize,
+        penalty: i32,
+    ) -> bool {
+        unsafe {
+            let cigar = (*self.wf_aligner).cigar;
+            let num_ops = match (*self.wf_aligner).alignment_scope {
+                wfa::alignment_scope_t_compute_score => 0,
+                _ => pattern_length.max(text_length),
+            };
+            if num_ops > (*cigar).max_operations as usize {
+                return false;
+            }
+            std::ptr::write_bytes((*cigar).operations, op, num_ops);
+            (*cigar).begin_offset = 0;
+            (*cigar).end_offset = num_ops as i32;
+            (*cigar).score = self.get_distance().penalty_to_score(penalty);
+            (*cigar).end_v = pattern_length as i32;
+            (*cigar).end_h = text_length as i32;
+            (*cigar).has_misms = false;
+            (*cigar).cigar_length = 0;
+            (*self.wf_aligner).align_status.status = 0;
+            (*self.wf_aligner).align_status.score = penalty;
+        }
+        true
+    }
+
+    /// Writes the alignment of `a` against `b` when one of them is empty: a single gap over
+    /// the other one (`I` for text bases, `D` for pattern bases), whose bases covered by free
+    /// ends cost nothing. Returns `false` as `write_uniform_alignment` does.
+    fn write_empty_alignment(&self, pattern_length: usize, text_length: usize) -> bool {
+        let form = unsafe { (*self.wf_aligner).alignment_form };
+        let ends_free = form.span == wfa::alignment_span_t_alignment_endsfree;
+        let (op, length, free) = if pattern_length == 0 {
+            (b'I', text_length, form.text_begin_free + form.text_end_free)
+        } else {
+            (
+                b'D',
+                pattern_length,
+                form.pattern_begin_free + form.pattern_end_free,
+            )
+        };
+        let charged = if ends_free {
+            length.saturating_sub(free.max(0) as usize)
+        } else {
+            length
+        };
+        let penalty = match charged {
+            0 => 0,
+            charged => self.get_distance().gap_penalty(charged as i32),
+        };
+        self.write_uniform_alignment(op, pattern_length, text_length, penalty)
+    }
+
+    /// Runs the checks `align` makes before handing sequences of these lengths to WFA2-lib.
+    ///
+    /// Returns the status when they settle the alignment on their own: `TooLong` or
+    /// `CigarTooLong` with an empty CIGAR, or the single-gap alignment of an empty sequence.
+    fn check_lengths(&self, pattern_length: usize, text_length: usize) -> Option<AlignmentStatus> {
+        if pattern_length > i32::MAX as usize || text_length > i32::MAX as usize {
+            self.write_rejected_alignment();
+            return Some(AlignmentStatus::TooLong);
+        }
+        if let Some(max) = self.max_cigar_len {
+            let scope = unsafe { (*self.wf_aligner).alignment_scope };
+            if scope != wfa::alignment_scope_t_compute_score && pattern_length + text_length > max {
+                self.write_rejected_alignment();
+                return Some(AlignmentStatus::CigarTooLong);
+            }
+        }
+        if (pattern_length == 0 || text_length == 0)
+            && self.write_empty_alignment(pattern_length, text_length)
+        {
+            return Some(self.check_min_length(AlignmentStatus::Completed));
+        }
+        None
+    }
+
+    /// Applies `align`'s post-processing to the alignment WFA2-lib just computed: indel
+    /// left-alignment (only given the aligned `sequences`, as it compares their bases), the gap
+    /// preference and the minimum length.
+    fn finish_alignment(
+        &self,
+        status: AlignmentStatus,
+        sequences: Option<(&[u8], &[u8])>,
+    ) -> AlignmentStatus {
+        if let (true, Some((pattern, text))) = (self.left_align_indels, sequences) {
+            left_align_indels(
+                unsafe { cigar_operations_mut((*self.wf_aligner).cigar) },
+                pattern,
+                text,
+            );
+        }
+        if let Some(preference) = self.gap_preference {
+            self.apply_gap_preference(preference);
+        }
+        self.check_min_length(status)
+    }
+
+    /// Align two sequences and return the alignment status.
+    ///
+    /// Sequences longer than `i32::MAX` bases are rejected with `AlignmentStatus::TooLong` and
+    /// an empty CIGAR, as WFA2-lib takes lengths as 32-bit integers.
+    ///
+    /// Empty sequences are not handed to WFA2-lib: if either one is empty the alignment is
+    /// `Completed` with a single gap over the other (all `I` for an empty pattern, all `D` for
+    /// an empty text) and that gap's penalty, minus the bases free ends cover; two empty
+    /// sequences give an empty CIGAR and score 0. This only falls back to WFA2-lib if the CIGAR
+    /// buffer, sized by the previous alignment, cannot hold the gap.
+    pub fn align(&self, a: &[u8], b: &[u8]) -> AlignmentStatus {
+        if let Some(status) = self.check_lengths(a.len(), b.len()) {
+            return status;
+        }
+        if self.identical_fast_path && a == b && self.write_identical_alignment(a.len()) {
+            self.identical_shortcuts
+                .set(self.identical_shortcuts.get() + 1);
+            return self.check_min_length(AlignmentStatus::Completed);
+        }
+
+        unsafe {
+            let (pattern, text) = (a, b);
+            let a = slice::from_raw_parts(a.as_ptr() as *const i8, a.len());
+            let b = slice::from_raw_parts(b.as_ptr() as *const i8, b.len());
+
+            let alignment_status: AlignmentStatus = wfa::wavefront_align(
+                self.wf_aligner,
+                a.as_ptr(),
+                a.len() as i32,
+                b.as_ptr(),
+                b.len() as i32,
+            )
+            .into();
+
+            self.finish_alignment(alignment_status, Some((pattern, text)))
+        }
+    }
+
+    /// Like `align`, for sequences already checked by `Sequence::new`.
+    pub fn align_typed(&self, pattern: Sequence, text: Sequence) -> AlignmentStatus {
+        self.align(pattern.as_bytes(), text.as_bytes())
+    }
+
+    /// Turns a completed `status` into `TooShort` if the last CIGAR is below the minimum length.
+    fn check_min_length(&self, status: AlignmentStatus) -> AlignmentStatus {
+        let scope = unsafe { (*self.wf_aligner).alignment_scope };
+        if status == AlignmentStatus::Completed
+            && scope != wfa::alignment_scope_t_compute_score
+            && aligned_block_length(self.cigar()) < self.min_alignment_length
+        {
+            return AlignmentStatus::TooShort;
+        }
+        status
+    }
+
+    /// Aligns tiny pairs with a dense dynamic-programming matrix instead of WFA, whose setup
+    /// dominates below a few dozen bases (e.g. barcode matching).
+    ///
+    /// Pairs where both sequences have at most `max_len` bases are aligned end-to-end with
+    /// Gotoh's algorithm under the configured penalties, one gap matrix pair per gap model.
+    /// The score is the optimal one, as WFA's; among several optimal alignments the CIGAR may
+    /// differ from WFA's tie-breaking choice. Longer pairs, and all pairs on aligners that
+    /// are not end-to-end, go through `align`. The result is not stored in the aligner.
+    pub fn align_short(&self, a: &[u8], b: &[u8], max_len: usize) -> AlignmentResult {
+        let end_to_end = matches!(self.get_alignment_span(), AlignmentSpan::End2End);
+        if !end_to_end || a.len() > max_len || b.len() > max_len {
+            let status = self.align(a, b);
+            return self.result(status);
+        }
+
+        let distance = self.get_distance();
+        let (cigar, penalty) = dense_alignment(a, b, &distance);
+        AlignmentResult {
+            status: AlignmentStatus::Completed,
+            score: distance.penalty_to_score(penalty),
+            cigar,
+        }
+    }
+
+    /// Returns the largest combined length (pattern plus text) whose alignment score is
+    /// guaranteed to fit in an `i32` under the configured penalties.
+    ///
+    /// An alignment has at most `pattern_len + text_len` columns, and no optimal alignment
+    /// costs more per column than the largest of the mismatch penalty and the cheapest gap's
+    /// opening plus extension (a gap of `n` bases never costs more than `n` times its first
+    /// base). The bound is `i32::MAX` divided by that per-column maximum; `align_checked`
+    /// rejects longer inputs.
+    pub fn max_representable_length(&self) -> usize {
+        (i32::MAX / self.get_distance().max_column_penalty()) as usize
+    }
+
+    /// Like `align`, but returns the score and an owned CIGAR on success and an error for any
+    /// other status, so results cannot be read from an unfinished alignment.
+    ///
+    /// Inputs whose score could overflow an `i32` (see `max_representable_length`) are refused
+    /// up front instead of letting the score wrap.
+    pub fn align_checked(&self, a: &[u8], b: &[u8]) -> Result<Alignment, AlignmentError> {
+        let max = self.max_representable_length();
+        let length = a.len().saturating_add(b.len());
+        if length > max {
+            return Err(AlignmentError::ScoreOverflow { length, max });
+        }
+
+        match self.align(a, b) {
+            AlignmentStatus::Completed => Ok(Alignment {
+                score: self.score(),
+                cigar: self.cigar().to_vec(),
+            }),
+            status => Err(AlignmentError::Status(status)),
+        }
+    }
+
+    /// Computes only the score of aligning `a` against `b`, without a backtrace or CIGAR, e.g.
+    /// on the hot path of clustering.
+    ///
+    /// Goes through `align`, so length limits, empty and identical inputs are handled the same
+    /// way. A score-only aligner aligns directly. Any other aligner is left untouched: as
+    /// WFA2-lib fixes the scope when an aligner is created (see `set_alignment_scope`), a
+    /// score-only copy is created on the first call and kept for the next ones, with the
+    /// current span, heuristic and limits applied before each call. Errors are as in
+    /// `align_checked`.
+    pub fn score_only(&self, a: &[u8], b: &[u8]) -> Result<i32, AlignmentError> {
+        let max = self.max_representable_length();
+        let length = a.len().saturating_add(b.len());
+        if length > max {
+            return Err(AlignmentError::ScoreOverflow { length, max });
+        }
+
+        let scope = unsafe { (*self.wf_aligner).alignment_scope };
+        let (status, score) = if scope == wfa::alignment_scope_t_compute_score {
+            (self.align(a, b), self.score())
+        } else {
+            let mut copy = self.score_only_copy.borrow_mut();
+            let scorer = copy.get_or_insert_with(|| {
+                let mut attributes = self.current_attributes();
+                attributes.alignment_scope = wfa::alignment_scope_t_compute_score;
+                Box::new(self.with_attributes(attributes))
+            });
+            unsafe {
+                let aligner = &*self.wf_aligner;
+                scorer.set_alignment_form(&aligner.alignment_form);
+                (*scorer.wf_aligner).heuristic = aligner.heuristic;
+                (*scorer.wf_aligner).system = aligner.system;
+            }
+            scorer.identical_fast_path = self.identical_fast_path;
+            (scorer.align(a, b), scorer.score())
+        };
+        match status {
+            AlignmentStatus::Completed => Ok(score),
+            status => Err(AlignmentError::Status(status)),
+        }
+    }
+
+    /// Aligns `a` against `b` in overlapping windows and stitches the per-window CIGARs.
+    ///
+    /// Each window covers up to `chunk_size` bases of both sequences and is aligned with the
+    /// current configuration. Only the head of each window alignment is kept, up to the first
+    /// match after `chunk_size - overlap` bases of either sequence have been consumed; the rest
+    /// is realigned as the start of the next window, so the arbitrary window end never shows up
+    /// in the output. The last window is kept whole.
+    ///
+    /// WFA2-lib cannot resume an alignment from a previous wavefront, so every window starts
+    /// from scratch; this is the way to align long pairs piece by piece.
+    ///
+    /// This trades optimality for bounded memory: indels longer than `overlap`, or divergence
+    /// that drifts away from the window diagonal, can yield a worse alignment than `align`.
+    /// The score is recomputed from the stitched CIGAR. Pairs fitting in a single window give
+    /// the same result as `align`. Meant for end-to-end aligners; stops at the first window that
+    /// does not complete and returns its status with the CIGAR stitched so far.
+    ///
+    /// Panics if `overlap >= chunk_size`.
+    pub fn align_chunked(
+        &self,
+        a: &[u8],
+        b: &[u8],
+        chunk_size: usize,
+        overlap: usize,
+    ) -> AlignmentResult {
+        assert!(
+            overlap < chunk_size,
+            "overlap must be smaller than chunk_size"
+        );
+        let step = chunk_size - overlap;
+        let distance = self.get_distance();
+
+        let mut cigar = Vec::with_capacity(a.len() + b.len());
+        let mut status = AlignmentStatus::Completed;
+        let (mut i, mut j) = (0, 0);
+        loop {
+            if i == a.len() || j == b.len() {
+                cigar.extend(std::iter::repeat_n(b'D', a.len() - i));
+                cigar.extend(std::iter::repeat_n(b'I', b.len() - j));
+                break;
+            }
+
+            let a_end = (i + chunk_size).min(a.len());
+            let b_end = (j + chunk_size).min(b.len());
+            status = self.align(&a[i..a_end], &b[j..b_end]);
+            if !matches!(status, AlignmentStatus::Completed) {
+                break;
+            }
+
+            let window = self.cigar();
+            if a_end == a.len() && b_end == b.len() {
+                if cigar.is_empty() {
+                    return self.result(status);
+                }
+                cigar.extend_from_slice(window);
+                break;
+            }
+
+            // Cut before the first match past the step so gaps are never split across windows.
+            let (mut di, mut dj) = (0, 0);
+            let mut cut = window.len();
+            let mut threshold = None;
+            for (idx, &op) in window.iter().enumerate() {
+                if di >= step || dj >= step {
+                    let first = *threshold.get_or_insert((idx, di, dj));
+                    if op == b'M' {
+                        cut = idx;
+                        break;
+                    }
+                    if idx == window.len() - 1 {
+                        (cut, di, dj) = first;
+                        break;
+                    }
+                }
+                match op {
+                    b'M' | b'X' => {
+                        di += 1;
+                        dj += 1;
+                    }
+                    b'I' => dj += 1,
+                    b'D' => di += 1,
+                    _ => {}
+                }
+            }
+            cigar.extend_from_slice(&window[..cut]);
+            i += di;
+            j += dj;
+        }
+
+        AlignmentResult {
+            status,
+            score: distance.penalty_to_score(distance.cigar_penalty(&cigar)),
+            cigar,
+        }
+    }
+
+    /// Compares equal-length sequences position by position, running the full alignment only
+    /// when they differ in more than `max_mismatches` positions or have different lengths.
+    ///
+    /// The ungapped alignment is returned as is, scored with the mismatch penalty. It is
+    /// optimal as long as no gapped alignment beats it, which holds when `max_mismatches`
+    /// mismatches cost no more than the cheapest pair of gaps that could realign them (two
+    /// single-base gaps); with larger thresholds, a shifted repeat can be reported as a series
+    /// of mismatches instead of an indel pair. Meant for end-to-end aligners.
+    pub fn hamming_or_align(&self, a: &[u8], b: &[u8], max_mismatches: usize) -> AlignmentResult {
+        if a.len() == b.len() {
+            let mismatches = a.iter().zip(b).filter(|(x, y)| x != y).count();
+            if mismatches <= max_mismatches {
+                let distance = self.get_distance();
+                let cigar = a
+                    .iter()
+                    .zip(b)
+                    .map(|(x, y)| if x == y { b'M' } else { b'X' })
+                    .collect();
+                return AlignmentResult {
+                    status: AlignmentStatus::Completed,
+                    score: distance
+                        .penalty_to_score(mismatches as i32 * distance.mismatch_penalty()),
+                    cigar,
+                };
+            }
+        }
+
+        let status = self.align(a, b);
+        self.result(status)
+    }
+
+    /// Translates a nucleotide `query` in each reading frame with `table`, aligns every
+    /// translation against `protein` and keeps the best-scoring frame.
+    ///
+    /// The three forward frames are always tried, and the three reverse-complement frames too
+    /// if `both_strands` is set. Completed alignments are preferred over others; ties keep the
+    /// first frame tried. The aligner's penalties apply to amino acids, so a mismatch costs the
+    /// same whatever the residues.
+    pub fn translated_align(
+        &self,
+        query: &[u8],
+        protein: &[u8],
+        table: &CodonTable,
+        both_strands: bool,
+    ) -> TranslatedAlignment {
+        let reverse = reverse_complement(query);
+        let mut strands = vec![(1, query)];
+        if both_strands {
+            strands.push((-1, &reverse[..]));
+        }
+
+        let mut best: Option<TranslatedAlignment> = None;
+        for (sign, strand) in strands {
+            for offset in 0..3 {
+                let translation = table.translate(strand.get(offset..).unwrap_or_default());
+                let status = self.align(&translation, protein);
+                let candidate = TranslatedAlignment {
+                    frame: sign * (offset as i8 + 1),
+                    translation,
+                    result: self.result(status),
+                };
+                // Scores share the sign of the metric, so compare penalties.
+                let rank = |alignment: &TranslatedAlignment| {
+                    (
+                        alignment.result.status != AlignmentStatus::Completed,
+                        alignment.result.score.unsigned_abs(),
+                    )
+                };
+                if best
+                    .as_ref()
+                    .is_none_or(|best| rank(&candidate) < rank(best))
+                {
+                    best = Some(candidate);
+                }
+            }
+        }
+        best.expect("at least one frame is aligned")
+    }
+
+    /// Aligns `query` against `reference` around a known shared seed, given as `(query_pos,
+    /// reference_pos, length)`, so the seed is not rediscovered by WFA.
+    ///
+    /// The flanks left and right of the seed are aligned separately and stitched with the seed
+    /// in between, so the alignment is forced through it; for a seed lying on the optimal path
+    /// this gives the same score as `align`. Seed bases that differ are reported as mismatches.
+    /// The score is recomputed from the stitched CIGAR, and the status is the first non-completed
+    /// status of the two flanks, if any. Meant for end-to-end aligners.
+    ///
+    /// Panics if the seed does not fit in both sequences.
+    pub fn align_anchored(
+        &self,
+        query: &[u8],
+        reference: &[u8],
+        anchor: (usize, usize, usize),
+    ) -> AlignmentResult {
+        let (query_pos, reference_pos, length) = anchor;
+        assert!(
+            query_pos + length <= query.len() && reference_pos + length <= reference.len(),
+            "anchor must fit in both sequences"
+        );
+        let distance = self.get_distance();
+
+        let mut status = AlignmentStatus::Completed;
+        let mut cigar = Vec::with_capacity(query.len() + reference.len());
+        let mut align_flank = |a: &[u8], b: &[u8], cigar: &mut Vec<u8>| {
+            if a.is_empty() || b.is_empty() {
+                cigar.extend(std::iter::repeat_n(b'D', a.len()));
+                cigar.extend(std::iter::repeat_n(b'I', b.len()));
+                return;
+            }
+            let flank_status = self.align(a, b);
+            if status == AlignmentStatus::Completed {
+                status = flank_status;
+            }
+            cigar.extend_from_slice(self.cigar());
+        };
+
+        align_flank(&query[..query_pos], &reference[..reference_pos], &mut cigar);
+        let seed = query[query_pos..query_pos + length]
+            .iter()
+            .zip(&reference[reference_pos..reference_pos + length]);
+        cigar.extend(seed.map(|(x, y)| if x == y { b'M' } else { b'X' }));
+        align_flank(
+            &query[query_pos + length..],
+            &reference[reference_pos + length..],
+            &mut cigar,
+        );
+
+        AlignmentResult {
+            status,
+            score: distance.penalty_to_score(distance.cigar_penalty(&cigar)),
+            cigar,
+        }
+    }
+
+    /// Aligns `query` against `reference` after stripping the leading and trailing `N` runs of
+    /// the query, so unknown bases at the read ends are soft-clipped instead of penalized.
+    ///
+    /// The returned CIGAR covers only `query[clip_start..query.len() - clip_end]`; interior `N`s
+    /// are aligned as regular bases. A query made only of `N`s leaves the whole reference
+    /// unaligned.
+    pub fn align_clipping_ns(&self, query: &[u8], reference: &[u8]) -> ClippedAlignment {
+        let is_n = |b: &&u8| b.eq_ignore_ascii_case(&b'N');
+        let clip_start = query.iter().take_while(is_n).count();
+        let clip_end = query[clip_start..].iter().rev().take_while(is_n).count();
+        let core = &query[clip_start..query.len() - clip_end];
+
+        let result = if core.is_empty() {
+            let distance = self.get_distance();
+            let penalty = match reference.len() {
+                0 => 0,
+                length => distance.gap_penalty(length as i32),
+            };
+            AlignmentResult {
+                status: AlignmentStatus::Completed,
+                score: distance.penalty_to_score(penalty),
+                cigar: vec![b'I'; reference.len()],
+            }
+        } else {
+            let status = self.align(core, reference);
+            self.result(status)
+        };
+
+        ClippedAlignment {
+            result,
+            clip_start,
+            clip_end,
+        }
+    }
+
+    /// Aligns `query` against a circular `reference`, allowing the alignment to wrap around
+    /// the origin.
+    ///
+    /// The query is aligned end-to-end against the reference concatenated with itself, with the
+    /// ends of the doubled reference free. The free leading/trailing reference bases are removed
+    /// from the returned CIGAR and the start is mapped back modulo the reference length, so an
+    /// alignment found in the second copy is reported at its position in the first one. Queries
+    /// longer than the reference can only be placed once around the circle. A doubled reference
+    /// longer than `i32::MAX` bases gives `TooLong`, as in `align`. The aligner's alignment span
+    /// is restored afterwards.
+    pub fn align_circular(&mut self, query: &[u8], reference: &[u8]) -> CircularAlignment {
+        let length = reference.len();
+        let doubled = [reference, reference].concat();
+        let saved_form = unsafe { (*self.wf_aligner).alignment_form };
+
+        unsafe {
+            wfa::wavefront_aligner_set_alignment_free_ends(
+                self.wf_aligner,
+                0,
+                0,
+                free_end_length(doubled.len()),
+                free_end_length(doubled.len()),
+            );
+        }
+        let status = self.align(query, &doubled);
+        let mut result = self.result(status);
+        self.set_alignment_form(&saved_form);
+
+        let leading = result.cigar.iter().take_while(|&&op| op == b'I').count();
+        let trailing = result.cigar[leading..]
+            .iter()
+            .rev()
+            .take_while(|&&op| op == b'I')
+            .count();
+        result.cigar.truncate(result.cigar.len() - trailing);
+        result.cigar.drain(..leading);
+
+        let reference_start = if length == 0 { 0 } else { leading % length };
+        let end = reference_start + cigar_spans(&result.cigar).1;
+        let spans_origin = end > length;
+        CircularAlignment {
+            result,
+            reference_start,
+            reference_end: if spans_origin { end - length } else { end },
+            spans_origin,
+        }
+    }
+
+    /// Aligns `query` against a suffix of `reference`, anchoring both right ends, e.g. for the
+    /// left extension of a seed in seed-and-extend.
+    ///
+    /// Both sequences are reversed and aligned with the end of the reversed reference free, so
+    /// gaps are placed as close to the anchor as WFA2-lib would place them at a left anchor. The
+    /// CIGAR is then reversed back: it reads in forward orientation and, as in WFA2-lib's own
+    /// ends-free output, starts with the unaligned reference prefix as `I` operations, which are
+    /// not part of the score. A reference longer than `i32::MAX` bases gives `TooLong`, as in
+    /// `align`. The aligner's alignment span is restored afterwards.
+    pub fn align_suffix(&mut self, query: &[u8], reference: &[u8]) -> AlignmentResult {
+        let query_rev: Vec<u8> = query.iter().rev().copied().collect();
+        let reference_rev: Vec<u8> = reference.iter().rev().copied().collect();
+        let saved_form = unsafe { (*self.wf_aligner).alignment_form };
+
+        unsafe {
+            wfa::wavefront_aligner_set_alignment_free_ends(
+                self.wf_aligner,
+                0,
+                0,
+                0,
+                free_end_length(reference.len()),
+            );
+        }
+        let status = self.align(&query_rev, &reference_rev);
+        let mut result = self.result(status);
+        self.set_alignment_form(&saved_form);
+
+        result.cigar.reverse();
+        result
+    }
+
+    /// Aligns `a` reversed, without complementing, against `b`, e.g. to detect palindromic or
+    /// mirrored structure, also in non-nucleotide data. The CIGAR refers to the reversed `a`.
+    ///
+    /// The reversal is a single exact-size copy of `a`; `b` is used in place.
+    pub fn align_reversed(&self, a: &[u8], b: &[u8]) -> AlignmentStatus {
+        let reversed: Vec<u8> = a.iter().rev().copied().collect();
+        self.align(&reversed, b)
+    }
+
+    /// Aligns `query` and its reverse complement against `reference` and keeps the better one,
+    /// whose CIGAR and score stay in the aligner. `N` and other non-`ACGT` bases are kept as
+    /// they are, and the case of each base is preserved.
+    ///
+    /// Completed alignments win over others, then the lower penalty; ties go to the forward
+    /// strand. The reverse strand is aligned first, so the forward strand's alignment is already
+    /// in place when it wins; only a winning reverse strand is aligned a second time.
+    pub fn align_both_strands(&self, query: &[u8], reference: &[u8]) -> (Strand, AlignmentStatus) {
+        let rank = |status: &AlignmentStatus| {
+            (
+                *status != AlignmentStatus::Completed,
+                self.score().unsigned_abs(),
+            )
+        };
+        let reverse_complemented = reverse_complement(query);
+        let reverse_status = self.align(&reverse_complemented, reference);
+        let reverse = rank(&reverse_status);
+        let forward_status = self.align(query, reference);
+        if reverse < rank(&forward_status) {
+            (
+                Strand::Reverse,
+                self.align(&reverse_complemented, reference),
+            )
+        } else {
+            (Strand::Forward, forward_status)
+        }
+    }
+
+    /// Places both reads of a pair on `reference` and checks that they are consistent with a
+    /// forward-reverse (Illumina paired-end) library with an insert size in `insert_size`.
+    ///
+    /// Each read is aligned on both strands against the whole reference with free reference
+    /// ends, and the strand with the better alignment is kept (completed alignments first, then
+    /// lower penalty). The pair is concordant when both alignments completed, the reads are on
+    /// opposite strands with the forward one starting first, and the insert size is in range.
+    /// A reference longer than `i32::MAX` bases gives `TooLong` for both reads, as in `align`.
+    /// The aligner's alignment span is restored afterwards.
+    pub fn align_pair(
+        &mut self,
+        read1: &[u8],
+        read2: &[u8],
+        reference: &[u8],
+        insert_size: std::ops::RangeInclusive<usize>,
+    ) -> PairedAlignment {
+        let saved_form = unsafe { (*self.wf_aligner).alignment_form };
+        unsafe {
+            wfa::wavefront_aligner_set_alignment_free_ends(
+                self.wf_aligner,
+                0,
+                0,
+                free_end_length(reference.len()),
+                free_end_length(reference.len()),
+            );
+        }
+
+        let place = |read: &[u8]| {
+            let rank = |result: &AlignmentResult| {
+                (
+                    result.status != AlignmentStatus::Completed,
+                    result.score.unsigned_abs(),
+                )
+            };
+            let status = self.align(read, reference);
+            let forward = self.result(status);
+            let status = self.align(&reverse_complement(read), reference);
+            let reverse = self.result(status);
+            let (result, orientation) = if rank(&reverse) < rank(&forward) {
+                (reverse, Orientation::Reverse)
+            } else {
+                (forward, Orientation::Forward)
+            };
+            let (start, end) = coverage_interval(&result.cigar);
+            PlacedRead {
+                result,
+                orientation,
+                reference_range: start..end,
+            }
+        };
+        let read1 = place(read1);
+        let read2 = place(read2);
+        self.set_alignment_form(&saved_form);
+
+        let start = read1.reference_range.start.min(read2.reference_range.start);
+        let end = read1.reference_range.end.max(read2.reference_range.end);
+        let (first, second) = if read1.reference_range.start <= read2.reference_range.start {
+            (&read1, &read2)
+        } else {
+            (&read2, &read1)
+        };
+        let concordant = read1.result.status == AlignmentStatus::Completed
+            && read2.result.status == AlignmentStatus::Completed
+            && first.orientation == Orientation::Forward
+            && second.orientation == Orientation::Reverse
+            && insert_size.contains(&(end - start));
+
+        PairedAlignment {
+            read1,
+            read2,
+            insert_size: end - start,
+            concordant,
+        }
+    }
+
+    /// Looks for a dovetail overlap between a suffix of `read_a` and a prefix of `read_b`.
+    ///
+    /// The reads are aligned ends-free with all but the last `min_overlap` bases of `read_a`
+    /// free at its start and all but the first `min_overlap` bases of `read_b` free at its end,
+    /// plus up to `max_overhang` unaligned bases at the end of `read_a` and the start of
+    /// `read_b`, so the trivial alignment that skips both reads is never chosen. Returns `None`
+    /// if the alignment does not complete (including `TooLong` reads) or the overlap is shorter
+    /// than `min_overlap` on either read. The aligner's alignment span is restored afterwards.
+    pub fn overlap_align(
+        &mut self,
+        read_a: &[u8],
+        read_b: &[u8],
+        max_overhang: usize,
+        min_overlap: usize,
+    ) -> Option<Overlap> {
+        let saved_form = unsafe { (*self.wf_aligner).alignment_form };
+        unsafe {
+            wfa::wavefront_aligner_set_alignment_free_ends(
+                self.wf_aligner,
+                free_end_length(read_a.len().saturating_sub(min_overlap)),
+                free_end_length(max_overhang),
+                free_end_length(max_overhang),
+                free_end_length(read_b.len().saturating_sub(min_overlap)),
+            );
+        }
+        let status = self.align(read_a, read_b);
+        let result = self.result(status);
+        self.set_alignment_form(&saved_form);
+        if result.status != AlignmentStatus::Completed {
+            return None;
+        }
+
+        // Terminal gaps are the free, unaligned ends of both reads.
+        let is_gap = |op: &&u8| **op == b'I' || **op == b'D';
+        let leading = result.cigar.iter().take_while(is_gap).count();
+        let trailing = result.cigar[leading..]
+            .iter()
+            .rev()
+            .take_while(is_gap)
+            .count();
+        let core = &result.cigar[leading..result.cigar.len() - trailing];
+        let (a_start, b_start) = cigar_spans(&result.cigar[..leading]);
+        let (a_len, b_len) = cigar_spans(core);
+        if a_len < min_overlap || b_len < min_overlap {
+            return None;
+        }
+
+        Some(Overlap {
+            a_range: a_start..a_start + a_len,
+            b_range: b_start..b_start + b_len,
+            score: result.score,
+            cigar: core.to_vec(),
+        })
+    }
+
+    /// Aligns `pattern` against `text` letting the leading and trailing lowercase runs of the
+    /// pattern fall into free ends, so soft-masked low-quality tails are clipped rather than
+    /// counted as mismatches.
+    ///
+    /// Both sequences are compared case-insensitively, so interior lowercase bases are aligned
+    /// like any other base. Text ends are never free. The aligner's alignment span is restored
+    /// afterwards; see `suggested_soft_clips` for the resulting clip points.
+    pub fn align_soft_masked(&mut self, pattern: &[u8], text: &[u8]) -> AlignmentStatus {
+        let (head, tail) = soft_masked_ends(pattern);
+        let saved_form = unsafe { (*self.wf_aligner).alignment_form };
+
+        unsafe {
+            wfa::wavefront_aligner_set_alignment_free_ends(
+                self.wf_aligner,
+                head as i32,
+                tail as i32,
+                0,
+                0,
+            );
+        }
+        let status = self.align(&pattern.to_ascii_uppercase(), &text.to_ascii_uppercase());
+
+        self.set_alignment_form(&saved_form);
+        status
+    }
+
+    /// Returns how many pattern bases to soft-clip at the start and end of the last alignment.
+    ///
+    /// Only lowercase bases of the leading and trailing soft-masked runs of `pattern` are
+    /// clipped, and only while they are not aligned as matches. `pattern` must be the one
+    /// passed to the last `align_soft_masked` (or `align`) call.
+    pub fn suggested_soft_clips(&self, pattern: &[u8]) -> (usize, usize) {
+        let (head, tail) = soft_masked_ends(pattern);
+        let clippable = |op: u8| op == b'D' || op == b'X';
+        let cigar = self.cigar();
+
+        let begin_clip = cigar
+            .iter()
+            .take_while(|&&op| clippable(op))
+            .count()
+            .min(head);
+        let end_clip = cigar
+            .iter()
+            .rev()
+            .take_while(|&&op| clippable(op))
+            .count()
+            .min(tail);
+
+        (begin_clip, end_clip)
+    }
+
+    /// Status reported by the last alignment.
+    fn last_status(&self) -> AlignmentStatus {
+        unsafe { (*self.wf_aligner).align_status.status }.into()
+    }
+
+    /// Copies the last alignment out of the aligner.
+    fn result(&self, status: AlignmentStatus) -> AlignmentResult {
+        AlignmentResult {
+            status,
+            score: self.score(),
+            cigar: self.cigar().to_vec(),
+        }
+    }
+
+    /// Copies the last alignment out of the aligner, with the status WFA2-lib recorded for it
+    /// (Rust-side checks such as `TooShort` are only reported by `align`).
+    pub fn take_result(&self) -> AlignmentResult {
+        self.result(self.last_status())
+    }
+
+    /// Returns the last alignment's CIGAR, one operation per column, with matches written as `=`.
+    ///
+    /// WFA2-lib always records matches (`M`) and mismatches (`X`) apart, so this only renames
+    /// `M`; `I` (text only) and `D` (pattern only) are unchanged. There is no switch to collapse
+    /// them: every aligner computing a CIGAR keeps them apart, and score-only aligners record
+    /// none.
+    pub fn cigar_extended(&self) -> Vec<u8> {
+        self.cigar()
+            .iter()
+            .map(|&op| if op == b'M' { b'=' } else { op })
+            .collect()
+    }
+
+    /// Returns a copy of the CIGAR of the last alignment, which stays valid across later
+    /// alignments (see `cigar`).
+    pub fn cigar_owned(&self) -> Vec<u8> {
+        self.cigar().to_vec()
+    }
+
+    /// Returns the CIGAR string from the last alignment, empty for score-only aligners.
+    ///
+    /// The slice points into the aligner's CIGAR buffer, which the next alignment overwrites.
+    /// Since `align` only borrows the aligner immutably, the compiler does not stop a slice from
+    /// being held across it; copy it with `cigar_owned` (or `take_result`) before aligning again.
+    pub fn cigar(&self) -> &[u8] {
+        unsafe {
+            if (*self.wf_aligner).alignment_scope == wfa::alignment_scope_t_compute_score {
+                return &[];
+            }
+            let cigar = (*self.wf_aligner).cigar;
+            let ops = (*cigar).operations;
+            let begin_offset = (*cigar).begin_offset;
+            let end_offset = (*cigar).end_offset;
+            let length = end_offset - begin_offset;
+
+            let cigar_slice: &[u8] = std::slice::from_raw_parts(
+                (ops as *const u8).add(begin_offset as usize),
+                length.try_into().unwrap(),
+            );
+            cigar_slice
+        }
+    }
+
+    /// Returns how many reference (text) bases the last alignment consumes: matches,
+    /// mismatches and the text-only `I` operations.
+    pub fn reference_span(&self) -> usize {
+        cigar_spans(self.cigar()).1
+    }
+
+    /// Returns whether the last alignment's path touches the edge of the static band, a sign
+    /// that the band may be too narrow and the optimal alignment may have been cut off.
+    ///
+    /// The path's diagonal (text position minus pattern position, as in WFA2-lib) is tracked
+    /// along the CIGAR: `I` moves it up and `D` down. Only `HeuristicStrategy::BandedStatic`
+    /// is checked, since the adaptive band moves with the wavefront; without a static band this
+    /// returns `false`.
+    pub fn path_reached_band_edge(&self) -> bool {
+        let Some((min_k, max_k)) = self.get_heuristics().iter().find_map(|h| match h {
+            HeuristicStrategy::BandedStatic {
+                band_min_k,
+                band_max_k,
+            } => Some((*band_min_k, *band_max_k)),
+            _ => None,
+        }) else {
+            return false;
+        };
+
+        let (lowest, highest) = diagonal_range(self.cigar());
+        lowest <= min_k || highest >= max_k
+    }
+
+    /// Returns the half-open interval of reference (text) positions the last alignment covers,
+    /// e.g. to add a read to a pileup coverage array.
+    ///
+    /// Leading and trailing reference-only (`I`) operations, such as the free ends of an
+    /// ends-free alignment, are not counted as covered. A CIGAR without any match or mismatch
+    /// gives an empty interval.
+    pub fn reference_coverage_interval(&self) -> (usize, usize) {
+        coverage_interval(self.cigar())
+    }
+
+    /// Returns where the last alignment starts and ends as half-open offsets
+    /// `(pattern_start, pattern_end, text_start, text_end)`, e.g. to place an ends-free or
+    /// heuristically cut alignment back onto the reference.
+    ///
+    /// Leading and trailing gaps, such as free ends, are not part of the aligned region: it runs
+    /// from the first to the last match or mismatch. An alignment stopped early (extension or
+    /// drop heuristics) has a CIGAR ending where WFA2-lib stopped, so the ends reflect that. A
+    /// CIGAR without any match or mismatch gives `(0, 0, 0, 0)`.
+    pub fn alignment_endpoints(&self) -> (usize, usize, usize, usize) {
+        let cigar = self.cigar();
+        let is_aligned = |op: &u8| *op == b'M' || *op == b'X';
+        let (Some(first), Some(last)) = (
+            cigar.iter().position(is_aligned),
+            cigar.iter().rposition(is_aligned),
+        ) else {
+            return (0, 0, 0, 0);
+        };
+        let (pattern_start, text_start) = cigar_spans(&cigar[..first]);
+        let (pattern_end, text_end) = cigar_spans(&cigar[..=last]);
+        (pattern_start, pattern_end, text_start, text_end)
+    }
+
+    /// Returns the 1-based reference position (SAM `POS`) of the first aligned base of the last
+    /// alignment, with `reference_offset` the 0-based position of the text in the reference.
+    ///
+    /// Leading query-only operations (`D`, shown as insertions or soft-clips in SAM) do not
+    /// move the position, while leading reference-only operations (`I`, e.g. a free text
+    /// prefix) do, as in `reference_coverage_interval`.
+    pub fn sam_pos(&self, reference_offset: usize) -> usize {
+        reference_offset + self.reference_coverage_interval().0 + 1
+    }
+
+    /// Returns how many query (pattern) bases the last alignment consumes: matches,
+    /// mismatches and the pattern-only `D` operations.
+    pub fn query_span(&self) -> usize {
+        cigar_spans(self.cigar()).0
+    }
+
+    /// Splits the last alignment at every indel of at least `min_indel` bases, e.g. to turn
+    /// one alignment into split-read structural variant evidence.
+    ///
+    /// Each block is the stretch of alignment between two large indels, with its own CIGAR and
+    /// coordinates; the large indels themselves are left out and show up as the breakpoints
+    /// between consecutive blocks (see `AlignedBlock::gap_to`).
+    pub fn split_at_large_indels(&self, min_indel: usize) -> Vec<AlignedBlock> {
+        let mut blocks = Vec::new();
+        let mut block = AlignedBlock {
+            query_range: 0..0,
+            reference_range: 0..0,
+            cigar: Vec::new(),
+        };
+
+        for (op, length) in self.cigar_runs() {
+            let length = length as usize;
+            let large = matches!(op, CigarOp::Insertion | CigarOp::Deletion) && length >= min_indel;
+            let (query_len, reference_len) = match op {
+                CigarOp::Match | CigarOp::Mismatch => (length, length),
+                CigarOp::Insertion => (0, length),
+                CigarOp::Deletion => (length, 0),
+            };
+
+            if large {
+                let query_pos = block.query_range.end + query_len;
+                let reference_pos = block.reference_range.end + reference_len;
+                let next = AlignedBlock {
+                    query_range: query_pos..query_pos,
+                    reference_range: reference_pos..reference_pos,
+                    cigar: Vec::new(),
+                };
+                let done = std::mem::replace(&mut block, next);
+                if !done.cigar.is_empty() {
+                    blocks.push(done);
+                }
+            } else {
+                block.query_range.end += query_len;
+                block.reference_range.end += reference_len;
+                block
+                    .cigar
+                    .extend(std::iter::repeat_n(op.to_byte(), length));
+            }
+        }
+        if !block.cigar.is_empty() {
+            blocks.push(block);
+        }
+        blocks
+    }
+
+    /// Returns the operation counts of the last alignment.
+    pub fn cigar_stats(&self) -> AlignmentStats {
+        AlignmentStats::from_cigar(self.cigar())
+    }
+
+    /// Returns the dissimilarity of the last alignment in `[0, 1]`, as defined by
+    /// `AlignmentStats::normalized_distance`: 0 for identical sequences, 1 when no column
+    /// is a match.
+    ///
+    /// This is not a true metric. WFA2-lib breaks ties between optimal alignments differently
+    /// depending on which sequence is the pattern, so `d(a, b)` and `d(b, a)` can differ, and
+    /// the triangle inequality is not guaranteed. Align pairs in a fixed order (or average both
+    /// directions) before feeding the values to UPGMA or neighbor-joining.
+    pub fn normalized_distance(&self) -> f64 {
+        self.cigar_stats().normalized_distance()
+    }
+
+    /// Returns status, score, operation counts, spans and identity of the last alignment.
+    pub fn summary(&self) -> AlignmentSummary {
+        let stats = self.cigar_stats();
+        AlignmentSummary {
+            status: self.last_status(),
+            score: self.score(),
+            matches: stats.matches,
+            mismatches: stats.mismatches,
+            insertions: stats.insertions,
+            deletions: stats.deletions,
+            query_span: self.query_span(),
+            reference_span: self.reference_span(),
+            block_identity: stats.block_identity(),
+        }
+    }
+
+    /// Serializes `summary()` as a single-line JSON object, e.g.
+    ///
+    /// ```json
+    /// {"status":"Completed","score":-24,"matches":28,"mismatches":2,"insertions":1,
+    ///  "deletions":1,"query_span":31,"reference_span":31,"block_identity":0.875}
+    /// ```
+    ///
+    /// Field names follow `AlignmentSummary` and are part of the stable API.
+    #[cfg(feature = "serde")]
+    pub fn stats_json(&self) -> String {
+        serde_json::to_string(&self.summary()).expect("AlignmentSummary is always serializable")
+    }
+
+    /// Returns the operations of the last alignment's CIGAR, one per column.
+    pub fn cigar_ops(&self) -> impl Iterator<Item = CigarOp> + '_ {
+        self.cigar().iter().filter_map(|&op| CigarOp::from_byte(op))
+    }
+
+    /// Returns the last alignment's CIGAR run-length encoded, as SAM/BAM need it. Empty for an
+    /// empty alignment.
+    pub fn cigar_run_length(&self) -> Vec<(CigarOp, u32)> {
+        self.cigar_runs().collect()
+    }
+
+    /// Returns the runs of identical operations of the last alignment's CIGAR.
+    pub fn cigar_runs(&self) -> CigarOps<'_> {
+        CigarOps::new(self.cigar())
+    }
+
+    /// Returns the last alignment's CIGAR in SAM format, e.g. `3M1I5M`, with the pattern as the
+    /// query and the text as the reference.
+    ///
+    /// Matches and mismatches are both written as `M`, or as `=` and `X` if `extended` is set.
+    /// WFA2-lib's `I` (text-only) and `D` (pattern-only) operations become SAM `D` and `I`. An
+    /// empty CIGAR gives `*`, as in SAM.
+    pub fn cigar_sam(&self, extended: bool) -> String {
+        sam_cigar(self.cigar(), false, extended)
+    }
+
+    /// Returns the last alignment's CIGAR as SAM/BAM `(length, operation)` runs, e.g. to build
+    /// a rust-htslib `CigarString`, with the pattern as the query and the text as the reference.
+    ///
+    /// Matches are `=` and mismatches `X` (`Cigar::Equal` and `Cigar::Diff`); merge them into
+    /// `M` runs if the target expects that. WFA2-lib's `I` (text-only) and `D` (pattern-only)
+    /// operations become `D` and `I`, as in `cigar_sam`. An empty CIGAR gives no runs.
+    pub fn cigar_htslib(&self) -> Vec<(u32, char)> {
+        sam_cigar_runs(self.cigar(), false, true)
+            .into_iter()
+            .map(|(op, length)| (length, op as char))
+            .collect()
+    }
+
+    /// Checks that the last CIGAR is a valid alignment of `pattern` against `text`, e.g. as a
+    /// cheap self-check of WFA2-lib's backtrace.
+    ///
+    /// Following WFA2-lib's convention, `M` and `X` consume a base of both sequences, `I` a
+    /// text base and `D` a pattern base. `M` columns must pair equal bases, `X` columns
+    /// different ones (both compared as bytes, as WFA2-lib does), and the CIGAR must consume
+    /// both sequences entirely: ends-free alignments pass as WFA2-lib spells out their free ends
+    /// as gaps, but extensions stopped early are reported as `Unconsumed`. Score-only aligners
+    /// have an empty CIGAR, which only verifies against empty sequences.
+    pub fn verify_cigar(&self, pattern: &[u8], text: &[u8]) -> Result<(), CigarVerifyError> {
+        let (mut i, mut j) = (0, 0);
+        for (column, &op) in self.cigar().iter().enumerate() {
+            let (uses_pattern, uses_text) = match op {
+                b'M' | b'X' => (true, true),
+                b'I' => (false, true),
+                b'D' => (true, false),
+                operation => return Err(CigarVerifyError::InvalidOperation { column, operation }),
+            };
+            if uses_pattern && i >= pattern.len() {
+                return Err(CigarVerifyError::PatternOverrun { column });
+            }
+            if uses_text && j >= text.len() {
+                return Err(CigarVerifyError::TextOverrun { column });
+            }
+            match op {
+                b'M' if pattern[i] != text[j] => {
+                    return Err(CigarVerifyError::NotAMatch { column })
+                }
+                b'X' if pattern[i] == text[j] => {
+                    return Err(CigarVerifyError::NotAMismatch { column })
+                }
+                _ => {}
+            }
+            i += uses_pattern as usize;
+            j += uses_text as usize;
+        }
+        if i < pattern.len() || j < text.len() {
+            return Err(CigarVerifyError::Unconsumed {
+                pattern_left: pattern.len() - i,
+                text_left: text.len() - j,
+            });
+        }
+        Ok(())
+    }
+
+    /// Renders the last alignment of `pattern` against `text` as the classic three-line view:
+    /// pattern row, `|` under matches (space for mismatches and gaps), and text row, with `-`
+    /// for the missing base of each gap.
+    ///
+    /// Rows are wrapped into blocks of `width` columns separated by a blank line; a `width` of
+    /// 0 keeps everything on one block. Every row ends with a newline. Bases the CIGAR points
+    /// past the end of the given sequences are shown as `?`.
+    pub fn format_alignment(&self, pattern: &[u8], text: &[u8], width: usize) -> String {
+        let base = |seq: &[u8], at: usize| seq.get(at).copied().unwrap_or(b'?');
+        let mut rows = [Vec::new(), Vec::new(), Vec::new()];
+        for (op, i, j) in cigar_columns(self.cigar()) {
+            let (top, bar, bottom) = match op {
+                b'M' => (base(pattern, i), b'|', base(text, j)),
+                b'X' => (base(pattern, i), b' ', base(text, j)),
+                b'I' => (b'-', b' ', base(text, j)),
+                b'D' => (base(pattern, i), b' ', b'-'),
+                _ => continue,
+            };
+            rows[0].push(top);
+            rows[1].push(bar);
+            rows[2].push(bottom);
+        }
+
+        let width = if width == 0 {
+            rows[0].len().max(1)
+        } else {
+            width
+        };
+        let blocks: Vec<String> = (0..rows[0].len())
+            .step_by(width)
+            .map(|start| {
+                let end = (start + width).min(rows[0].len());
+                rows.iter()
+                    .map(|row| format!("{}\n", String::from_utf8_lossy(&row[start..end])))
+                    .collect()
+            })
+            .collect();
+        blocks.join("\n")
+    }
+
+    /// Returns a compact run-length summary of the last alignment for logs, with the operation
+    /// before its length: `=32` for 32 matches, `=10X1=21` for a single mismatch.
+    ///
+    /// Operations are `=` (match), `X` (mismatch), and WFA2-lib's `I` and `D`. Deliberately not
+    /// SAM: the length follows the operation, so it cannot be mistaken for a SAM CIGAR.
+    pub fn cigar_log_string(&self) -> String {
+        self.cigar_runs()
+            .map(|(op, length)| {
+                let op = match op {
+                    CigarOp::Match => '=',
+                    op => op.to_byte() as char,
+                };
+                format!("{}{}", op, length)
+            })
+            .collect()
+    }
+
+    /// Returns the alignment score from the last alignment.
+    pub fn score(&self) -> i32 {
+        unsafe {
+            let cigar = (*self.wf_aligner).cigar;
+            (*cigar).score
+        }
+    }
+
+    /// Returns the score WFA2-lib stored for the last alignment; same as `score()`.
+    pub fn wfa_score(&self) -> i32 {
+        self.score()
+    }
+
+    /// Rescores the last alignment's CIGAR with the configured penalties, in the same
+    /// convention as `score()`.
+    ///
+    /// With ends-free alignment, the leading and trailing gaps are only scored beyond the
+    /// configured free lengths. Score-only aligners have no CIGAR and always give 0.
+    pub fn computed_score(&self) -> i32 {
+        let form = unsafe { (*self.wf_aligner).alignment_form };
+        let mut cigar = self.cigar();
+        if form.span == wfa::alignment_span_t_alignment_endsfree {
+            let free = |op: u8, pattern_free: i32, text_free: i32| match op {
+                b'D' => pattern_free.max(0) as usize,
+                b'I' => text_free.max(0) as usize,
+                _ => 0,
+            };
+            if let Some(&first) = cigar.first() {
+                let begin_free = free(first, form.pattern_begin_free, form.text_begin_free);
+                let skip = cigar.iter().take_while(|&&op| op == first).count();
+                cigar = &cigar[skip.min(begin_free)..];
+            }
+            if let Some(&last) = cigar.last() {
+                let end_free = free(last, form.pattern_end_free, form.text_end_free);
+                let skip = cigar.iter().rev().take_while(|&&op| op == last).count();
+                cigar = &cigar[..cigar.len() - skip.min(end_free)];
+            }
+        }
+
+        let distance = self.get_distance();
+        distance.penalty_to_score(distance.cigar_penalty(cigar))
+    }
+
+    /// Returns the running score after each operation of the last alignment's CIGAR, in the
+    /// same convention as `score()`, e.g. to plot where penalties accumulate.
+    ///
+    /// Entry `t` is the score of the CIGAR prefix ending at operation `t`, so a gap shows its
+    /// opening penalty on its first operation and the extension on every following one (for
+    /// dual-cost gap-affine, the cheaper of the two gap costs at that length). The last entry
+    /// equals `score()` for completed end-to-end alignments; free ends of an ends-free alignment
+    /// are scored here, unlike in `computed_score`.
+    pub fn cumulative_scores(&self) -> Vec<i32> {
+        let distance = self.get_distance();
+        let mut scores = Vec::with_capacity(self.cigar().len());
+        let mut completed = 0;
+        for run in self.cigar().chunk_by(|x, y| x == y) {
+            let run_penalty = |length: usize| match run[0] {
+                b'X' => length as i32 * distance.mismatch_penalty(),
+                b'I' | b'D' => distance.gap_penalty(length as i32),
+                _ => 0,
+            };
+            for length in 1..=run.len() {
+                scores.push(distance.penalty_to_score(completed + run_penalty(length)));
+            }
+            completed += run_penalty(run.len());
+        }
+        scores
+    }
+
+    /// Returns where a local extension along the last alignment should stop, as the number of
+    /// CIGAR columns of the best-scoring prefix and that prefix's score.
+    ///
+    /// WFA2-lib scores are penalties only, so the running score of `cumulative_scores` never
+    /// rises; as in X-drop, each match is rewarded with `match_bonus` here, giving a prefix the
+    /// score `match_bonus * matches - penalty`. The empty prefix scores 0, and the first of
+    /// several equal peaks is returned.
+    pub fn best_scoring_prefix(&self, match_bonus: i32) -> (usize, i32) {
+        let mut matches = 0;
+        let mut best = (0, 0);
+        for (column, (&op, score)) in self
+            .cigar()
+            .iter()
+            .zip(self.cumulative_scores())
+            .enumerate()
+        {
+            if op == b'M' {
+                matches += 1;
+            }
+            let prefix_score = match_bonus * matches - score.unsigned_abs() as i32;
+            if prefix_score > best.1 {
+                best = (column + 1, prefix_score);
+            }
+        }
+        best
+    }
+
+    /// Extracts the best-scoring stretch of the last alignment, scoring each match with
+    /// `match_bonus` against the configured penalties as in `best_scoring_prefix`.
+    ///
+    /// Column scores come from `cumulative_scores`, so a gap cut by the window keeps its opening
+    /// penalty on its first column. When several stretches reach the best score, candidates are
+    /// the stretches ending at each peak, starting right after the first or the last lowest
+    /// point before it, and `policy` picks among them. Returns `None` if no stretch scores above 0.
+    pub fn local_alignment(
+        &self,
+        match_bonus: i32,
+        policy: LocalTiePolicy,
+    ) -> Option<LocalAlignment> {
+        let cigar = self.cigar();
+        // prefix[t]: score of the first t columns; matches[t]: matches among them.
+        let mut prefix = vec![0];
+        let mut matches = vec![0];
+        for (&op, score) in cigar.iter().zip(self.cumulative_scores()) {
+            let matched = matches.last().unwrap() + (op == b'M') as i32;
+            matches.push(matched);
+            prefix.push(match_bonus * matched - score.unsigned_abs() as i32);
+        }
+
+        // Running minimum of the prefix scores, with its first and last positions.
+        let mut candidates: Vec<(usize, usize)> = Vec::new();
+        let mut best = 0;
+        let (mut low, mut first_low, mut last_low) = (prefix[0], 0, 0);
+        for (end, &value) in prefix.iter().enumerate().skip(1) {
+            let score = value - low;
+            if score > best {
+                best = score;
+                candidates.clear();
+            }
+            if score == best && best > 0 {
+                candidates.push((first_low, end));
+                if last_low != first_low {
+                    candidates.push((last_low, end));
+                }
+            }
+            if value < low {
+                (low, first_low, last_low) = (value, end, end);
+            } else if value == low {
+                last_low = end;
+            }
+        }
+
+        let identity = |&(start, end): &(usize, usize)| {
+            (matches[end] - matches[start]) as f64 / (end - start) as f64
+        };
+        let (start, end) = match policy {
+            LocalTiePolicy::Leftmost => candidates.iter().min().copied(),
+            LocalTiePolicy::Longest => candidates
+                .iter()
+                .copied()
+                .min_by_key(|&(start, end)| (std::cmp::Reverse(end - start), start)),
+            LocalTiePolicy::HighestIdentity => {
+                candidates
+                    .iter()
+                    .copied()
+                    .reduce(|a, b| if identity(&b) > identity(&a) { b } else { a })
+            }
+        }?;
+
+        let (query_start, reference_start) = cigar_spans(&cigar[..start]);
+        let (query_len, reference_len) = cigar_spans(&cigar[start..end]);
+        Some(LocalAlignment {
+            columns: start..end,
+            query_range: query_start..query_start + query_len,
+            reference_range: reference_start..reference_start + reference_len,
+            score: best,
+            cigar: cigar[start..end].to_vec(),
+        })
+    }
+
+    /// Returns whether `wfa_score` and `computed_score` agree.
+    ///
+    /// They always should for completed end-to-end and ends-free alignments with a CIGAR; a
+    /// disagreement points to a bug in WFA2-lib, in these bindings, or to a CIGAR modified in
+    /// place. Extension alignments and unfinished alignments are not expected to agree.
+    pub fn scores_agree(&self) -> bool {
+        self.wfa_score() == self.computed_score()
+    }
+
+    /// Returns the last alignment as a diff-style edit script turning `a` into `b`.
+    ///
+    /// `a` and `b` must be the pattern and text passed to the last `align` call; bytes the CIGAR
+    /// points past the end of the given sequences are reported as `?`.
+    pub fn edit_script(&self, a: &[u8], b: &[u8]) -> Vec<EditOp> {
+        let base = |seq: &[u8], at: usize| seq.get(at).copied().unwrap_or(b'?');
+        cigar_columns(self.cigar())
+            .filter_map(|(op, i, j)| match op {
+                b'M' => Some(EditOp::Keep(base(a, i))),
+                b'X' => Some(EditOp::Replace(base(a, i), base(b, j))),
+                b'I' => Some(EditOp::Insert(base(b, j))),
+                b'D' => Some(EditOp::Delete(base(a, i))),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Reduces the last alignment to its differences, grouped into regions with up to `context`
+    /// matched bases of flank on each side, e.g. to emit one record per cluster of variants.
+    ///
+    /// Differences separated by at most `2 * context` matches are merged into one region, so
+    /// the flanks of consecutive regions never overlap; farther apart, they get their own
+    /// region. Flanks are shorter at the ends of the alignment. `query` and `reference` must be
+    /// the pattern and text passed to the last `align` call; sequences shorter than the
+    /// alignment give no regions.
+    pub fn compact_diff(&self, query: &[u8], reference: &[u8], context: usize) -> Vec<DiffRegion> {
+        let cigar = self.cigar();
+        let (query_span, reference_span) = cigar_spans(cigar);
+        if query_span > query.len() || reference_span > reference.len() {
+            return Vec::new();
+        }
+        let columns: Vec<_> = cigar_columns(cigar).collect();
+        let mut clusters: Vec<(usize, usize)> = Vec::new();
+        for (column, &(op, _, _)) in columns.iter().enumerate() {
+            if op == b'M' {
+                continue;
+            }
+            match clusters.last_mut() {
+                Some((_, last)) if column - *last - 1 <= 2 * context => *last = column,
+                _ => clusters.push((column, column)),
+            }
+        }
+
+        clusters
+            .into_iter()
+            .map(|(first, last)| {
+                let start = first.saturating_sub(context);
+                let end = (last + context + 1).min(columns.len());
+                let (_, i, j) = columns[start];
+                let (query_len, reference_len) = cigar_spans(&cigar[start..end]);
+                DiffRegion {
+                    query_range: i..i + query_len,
+                    reference_range: j..j + reference_len,
+                    cigar: cigar[start..end].to_vec(),
+                    query: query[i..i + query_len].to_vec(),
+                    reference: reference[j..j + reference_len].to_vec(),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the sequence complexity of the last alignment, as the Shannon entropy in bits of
+    /// the base composition of the aligned region, to flag alignments in low-complexity or
+    /// repetitive sequence.
+    ///
+    /// Both sequences contribute every base the alignment covers (case-insensitive): query bases
+    /// of `M`, `X` and `D` columns and reference bases of `M`, `X` and `I` columns. A homopolymer
+    /// gives 0 and DNA with uniform composition gives 2; dinucleotide repeats give at most 1. An
+    /// empty alignment gives 0. `query` and `reference` must be the pattern and text passed to
+    /// the last `align` call; bases the CIGAR points past the end of them are not counted.
+    pub fn alignment_complexity(&self, query: &[u8], reference: &[u8]) -> f64 {
+        let mut counts = [0usize; 256];
+        let mut count = |base: Option<&u8>| {
+            if let Some(base) = base {
+                counts[base.to_ascii_uppercase() as usize] += 1;
+            }
+        };
+        for (op, i, j) in cigar_columns(self.cigar()) {
+            if matches!(op, b'M' | b'X' | b'D') {
+                count(query.get(i));
+            }
+            if matches!(op, b'M' | b'X' | b'I') {
+                count(reference.get(j));
+            }
+        }
+
+        let total: usize = counts.iter().sum();
+        if total == 0 {
+            return 0.0;
+        }
+        counts
+            .iter()
+            .filter(|&&count| count > 0)
+            .map(|&count| {
+                let p = count as f64 / total as f64;
+                p * (1.0 / p).log2()
+            })
+            .sum()
+    }
+
+    /// Returns the indels of the last alignment that shorten or lengthen a homopolymer run, the
+    /// typical nanopore error.
+    ///
+    /// An indel run counts when all its bases are the same and the other sequence has that base
+    /// right before or after the gap; indels of mixed bases or next to different bases are left
+    /// out as random indels. Each event reports the whole run around the gap in both sequences.
+    /// `query` and `reference` must be the pattern and text passed to the last `align` call;
+    /// sequences shorter than the alignment give no events.
+    pub fn homopolymer_indels(&self, query: &[u8], reference: &[u8]) -> Vec<HomopolymerEvent> {
+        let (query_span, reference_span) = cigar_spans(self.cigar());
+        if query_span > query.len() || reference_span > reference.len() {
+            return Vec::new();
+        }
+
+        // Returns the start and length of the run of `base` around `seq[start..end]`.
+        let run_around = |seq: &[u8], start: usize, end: usize, base: u8| {
+            let before = seq[..start]
+                .iter()
+                .rev()
+                .take_while(|&&b| b == base)
+                .count();
+            let after = seq[end..].iter().take_while(|&&b| b == base).count();
+            (start - before, before + (end - start) + after)
+        };
+
+        let mut events = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        for (op, length) in self.cigar_runs() {
+            let length = length as usize;
+            let (gap, other, gap_pos, other_pos) = match op {
+                CigarOp::Match | CigarOp::Mismatch => {
+                    i += length;
+                    j += length;
+                    continue;
+                }
+                CigarOp::Insertion => (&reference[j..j + length], query, j, i),
+                CigarOp::Deletion => (&query[i..i + length], reference, i, j),
+            };
+
+            let base = gap[0];
+            let flanked = (other_pos > 0 && other[other_pos - 1] == base)
+                || other.get(other_pos) == Some(&base);
+            if gap.iter().all(|&b| b == base) && flanked {
+                let gap_seq = if op == CigarOp::Insertion {
+                    reference
+                } else {
+                    query
+                };
+                let (gap_start, gap_length) = run_around(gap_seq, gap_pos, gap_pos + length, base);
+                let (other_start, other_length) = run_around(other, other_pos, other_pos, base);
+                let ((query_pos, query_length), (reference_pos, reference_length)) =
+                    if op == CigarOp::Insertion {
+                        ((other_start, other_length), (gap_start, gap_length))
+                    } else {
+                        ((gap_start, gap_length), (other_start, other_length))
+                    };
+                events.push(HomopolymerEvent {
+                    base,
+                    query_pos,
+                    reference_pos,
+                    query_length,
+                    reference_length,
+                });
+            }
+
+            match op {
+                CigarOp::Insertion => j += length,
+                _ => i += length,
+            }
+        }
+        events
+    }
+
+    /// Returns the k-mers shared by both sequences along the last alignment, as
+    /// `(query_pos, reference_pos, kmer)`, e.g. to verify seeds.
+    ///
+    /// A window of `k` columns is yielded when all its columns are matches, so windows spanning
+    /// an indel or a mismatch are skipped. Windows slide one column at a time within each run of
+    /// matches. `query` and `reference` must be the pattern and text passed to the last `align`
+    /// call; windows where they disagree are skipped as well.
+    pub fn aligned_kmers<'a>(
+        &'a self,
+        query: &'a [u8],
+        reference: &'a [u8],
+        k: usize,
+    ) -> impl Iterator<Item = (usize, usize, &'a [u8])> + 'a {
+        self.cigar_runs()
+            .scan((0, 0), |(i, j), (op, length)| {
+                let length = length as usize;
+                let run = (op, *i, *j, length);
+                match op {
+                    CigarOp::Match | CigarOp::Mismatch => {
+                        *i += length;
+                        *j += length;
+                    }
+                    CigarOp::Insertion => *j += length,
+                    CigarOp::Deletion => *i += length,
+                }
+                Some(run)
+            })
+            .filter(move |&(op, _, _, length)| op == CigarOp::Match && k > 0 && length >= k)
+            .flat_map(move |(_, i, j, length)| {
+                (0..=length - k).map(move |offset| (i + offset, j + offset))
+            })
+            .filter(move |&(i, j)| query[i..i + k] == reference[j..j + k])
+            .map(move |(i, j)| (i, j, &query[i..i + k]))
+    }
+
+    /// Returns the `(pattern_pos, text_pos)` coordinates of every match in the last alignment,
+    /// e.g. to draw the alignment path on a dotplot. Both coordinates increase monotonically.
+    pub fn match_coordinates(&self) -> Vec<(usize, usize)> {
+        cigar_columns(self.cigar())
+            .filter(|&(op, _, _)| op == b'M')
+            .map(|(_, i, j)| (i, j))
+            .collect()
+    }
+
+    /// Returns how many score steps (wavefront computations) the last alignment performed.
+    ///
+    /// WFA2-lib does not keep a separate step counter; this reads the internal score the
+    /// alignment loop stopped at, which is exactly the number of steps since WFA advances one
+    /// score unit per step. For completed alignments without heuristics it equals the optimal
+    /// penalty; hitting `max_alignment_steps` reports the cap. Pruning heuristics do not reduce
+    /// it, but make the aligner stop earlier when they drop the alignment.
+    pub fn score_steps_taken(&self) -> u64 {
+        unsafe { (*self.wf_aligner).align_status.score.max(0) as u64 }
+    }
+
+    /// Returns the whole configuration of the aligner in one value, e.g. for structured logging.
+    pub fn config_snapshot(&self) -> AlignerSnapshot {
+        AlignerSnapshot {
+            distance: self.get_distance(),
+            penalties: self.get_penalties(),
+            heuristics: self.get_heuristics(),
+            scope: self.get_alignment_scope(),
+            span: self.get_alignment_span(),
+            memory_mode: self.get_memory_mode(),
+        }
+    }
+
+    /// Returns how much work the last alignment did, e.g. to compare heuristic settings.
+    ///
+    /// These are the counters WFA2-lib keeps on the aligner; it counts neither computed nor
+    /// extended cells, so those are not available. In the ultralow (bidirectional) memory mode
+    /// the work happens in internal sub-aligners and these counters do not reflect it.
+    pub fn run_metrics(&self) -> RunMetrics {
+        let aligner = unsafe { &*self.wf_aligner };
+        RunMetrics {
+            score_steps: self.score_steps_taken(),
+            null_steps: aligner.align_status.num_null_steps.max(0) as u64,
+            dropped: aligner.align_status.dropped,
+            diagonal_range: (
+                aligner.wf_components.historic_min_lo,
+                aligner.wf_components.historic_max_hi,
+            ),
+            memory_used: aligner.align_status.memory_used,
+        }
+    }
+
+    /// Restores the configuration the aligner was created with (penalties, heuristic, memory
+    /// mode, scope, span, limits and plot), undoing later `set_heuristic`, `set_memory_mode`,
+    /// `set_alignment_scope`, ... calls. A clone resets to the configuration its source was
+    /// created with.
+    ///
+    /// The underlying aligner is recreated, which also drops the last alignment and any grown
+    /// buffers. Settings kept on the Rust side (label, gap preference, CIGAR limits, ...) are
+    /// unaffected.
+    ///
+    /// # Panics
+    ///
+    /// If WFA2-lib fails to allocate the new aligner; the current one is then kept.
+    pub fn reset(&mut self) {
+        self.rebuild(self.initial_attributes);
+    }
+
+    /// Reclaims any extra buffers the underlying WFA aligner grew during the last run.
+    pub fn clear(&mut self) {
+        unsafe {
+            wfa::wavefront_aligner_reap(self.wf_aligner);
+        }
+    }
+
+    /// Report the size of the underlying WFA aligner in bytes.
+    pub fn get_aligner_size(&self) -> u64 {
+        unsafe { wfa::wavefront_aligner_get_size(self.wf_aligner) }
+    }
+
+    /// Splits the memory held by the aligner by component.
+    ///
+    /// The wavefront slab, backtrace buffer and sub-aligners are read from WFA2-lib, the same
+    /// way `get_aligner_size()` adds them up; only the CIGAR buffers are estimated (see
+    /// `MemoryBreakdown::cigar`).
+    pub fn memory_breakdown(&self) -> MemoryBreakdown {
+        unsafe {
+            let aligner = &*self.wf_aligner;
+            let slab = aligner.wavefront_slab;
+            let wavefronts = if slab.is_null() {
+                0
+            } else {
+                wfa::wavefront_slab_get_size(slab)
+            };
+            let bt_buffer = aligner.wf_components.bt_buffer;
+            let backtrace = if bt_buffer.is_null() {
+                0
+            } else {
+                wf_backtrace_buffer_get_size_allocated(bt_buffer)
+            };
+            let sub_aligners = if aligner.bialigner.is_null() {
+                0
+            } else {
+                wfa::wavefront_bialigner_get_size(aligner.bialigner)
+            };
+            let cigar = (*self.wf_aligner).cigar;
+            let capacity = (*cigar).max_operations.max(0) as u64;
+            let run_length = if (*cigar).cigar_buffer.is_null() {
+                0
+            } else {
+                4 * capacity
+            };
+            MemoryBreakdown {
+                wavefronts,
+                backtrace,
+                sub_aligners,
+                other: self
+                    .get_aligner_size()
+                    .saturating_sub(wavefronts + backtrace + sub_aligners),
+                cigar: capacity + run_length,
+            }
+        }
+    }
+
+    /// Returns the best and worst scores an optimal alignment of sequences with the given
+    /// lengths can get under the configured penalties, in the same convention as `score()`.
+    ///
+    /// The best case aligns all of the shorter sequence as matches and covers the length
+    /// difference with a single gap. The worst case is the cheaper of two trivial alignments
+    /// that any optimal one must beat: mismatching the whole shorter sequence plus a single gap,
+    /// or deleting one sequence and inserting the other. Raw scores can then be normalized as
+    /// `(score - worst) / (best - worst)`.
+    pub fn score_bounds(&self, pattern_len: usize, text_len: usize) -> (i32, i32) {
+        let distance = self.get_distance();
+        let gap = |length: usize| match length {
+            0 => 0,
+            _ => distance.gap_penalty(length as i32),
+        };
+
+        let shorter = pattern_len.min(text_len);
+        let length_diff = pattern_len.abs_diff(text_len);
+        let best = gap(length_diff);
+        let all_mismatch = shorter as i32 * distance.mismatch_penalty() + gap(length_diff);
+        let all_indel = gap(pattern_len) + gap(text_len);
+        let worst = all_mismatch.min(all_indel);
+
+        (
+            distance.penalty_to_score(best),
+            distance.penalty_to_score(worst),
+        )
+    }
+
+    fn set_distance_attr(attributes: &mut wfa::wavefront_aligner_attr_t, mode: &Distance) {
+        match mode {
+            Distance::Edit => {
+                attributes.distance_metric = wfa::distance_metric_t_edit;
+            }
+            Distance::GapLinear { mismatch, indel } => {
+                attributes.distance_metric = wfa::distance_metric_t_gap_linear;
+                attributes.linear_penalties.mismatch = *mismatch;
+                attributes.linear_penalties.indel = *indel;
+            }
+            Distance::GapAffine {
+                mismatch,
+                gap_opening,
+                gap_extension,
+            } => {
+                attributes.distance_metric = wfa::distance_metric_t_gap_affine;
+                attributes.affine_penalties.mismatch = *mismatch;
+                attributes.affine_penalties.gap_opening = *gap_opening;
+                attributes.affine_penalties.gap_extension = *gap_extension;
+            }
+            Distance::GapAffine2p {
+                mismatch,
+                gap_opening1,
+                gap_extension1,
+                gap_opening2,
+                gap_extension2,
+            } => {
+                attributes.distance_metric = wfa::distance_metric_t_gap_affine_2p;
+                attributes.affine2p_penalties.mismatch = *mismatch;
+                attributes.affine2p_penalties.gap_opening1 = *gap_opening1;
+                attributes.affine2p_penalties.gap_extension1 = *gap_extension1;
+                attributes.affine2p_penalties.gap_opening2 = *gap_opening2;
+                attributes.affine2p_penalties.gap_extension2 = *gap_extension2;
+            }
+        }
+    }
+
+    pub fn get_distance(&self) -> Distance {
+        unsafe {
+            let aligner = *self.aligner();
+            let metric = aligner.penalties.distance_metric;
+
+            match metric {
+                wfa::distance_metric_t_edit => Distance::Edit,
+                wfa::distance_metric_t_gap_linear => Distance::GapLinear {
+                    mismatch: aligner.penalties.linear_penalties.mismatch,
+                    indel: aligner.penalties.linear_penalties.indel,
+                },
+                wfa::distance_metric_t_gap_affine => Distance::GapAffine {
+                    mismatch: aligner.penalties.mismatch,
+                    gap_opening: aligner.penalties.gap_opening1,
+                    gap_extension: aligner.penalties.gap_extension1,
+                },
+                wfa::distance_metric_t_gap_affine_2p => Distance::GapAffine2p {
+                    mismatch: aligner.penalties.mismatch,
+                    gap_opening1: aligner.penalties.gap_opening1,
+                    gap_extension1: aligner.penalties.gap_extension1,
+                    gap_opening2: aligner.penalties.gap_opening2,
+                    gap_extension2: aligner.penalties.gap_extension2,
+                },
+                _ => Distance::Edit, // Default fallback
+            }
+        }
+    }
+
+    /// Returns the penalties of the aligner as plain numbers, e.g. for logging.
+    ///
+    /// Metrics with a single gap model report it as both models: gap-affine repeats its
+    /// opening and extension, and edit distance gives its implied unit costs (mismatch 1, gap
+    /// opening 0, gap extension 1).
+    pub fn get_penalties(&self) -> Penalties {
+        match self.get_distance() {
+            Distance::Edit => Penalties {
+                mismatch: 1,
+                gap_opening1: 0,
+                gap_extension1: 1,
+                gap_opening2: 0,
+                gap_extension2: 1,
+            },
+            Distance::GapLinear { mismatch, indel } => Penalties {
+                mismatch,
+                gap_opening1: 0,
+                gap_extension1: indel,
+                gap_opening2: 0,
+                gap_extension2: indel,
+            },
+            Distance::GapAffine {
+                mismatch,
+                gap_opening,
+                gap_extension,
+            } => Penalties {
+                mismatch,
+                gap_opening1: gap_opening,
+                gap_extension1: gap_extension,
+                gap_opening2: gap_opening,
+                gap_extension2: gap_extension,
+            },
+            Distance::GapAffine2p {
+                mismatch,
+                gap_opening1,
+                gap_extension1,
+                gap_opening2,
+                gap_extension2,
+            } => Penalties {
+                mismatch,
+                gap_opening1,
+                gap_extension1,
+                gap_opening2,
+                gap_extension2,
+            },
+        }
+    }
+
+    fn set_heuristic_attr(
+        attributes: &mut wfa::wavefront_aligner_attr_t,
+        heuristic: Option<&HeuristicStrategy>,
+    ) {
+        match heuristic {
+            Some(HeuristicStrategy::BandedStatic {
+                band_min_k,
+                band_max_k,
+            }) => {
+                attributes.heuristic.strategy =
+                    wfa::wf_heuristic_strategy_wf_heuristic_banded_static;
+                attributes.heuristic.min_k = *band_min_k;
+                attributes.heuristic.max_k = *band_max_k;
+            }
+            Some(HeuristicStrategy::BandedAdaptive {
+                band_min_k,
+                band_max_k,
+                score_steps,
+            }) => {
+                attributes.heuristic.strategy =
+                    wfa::wf_heuristic_strategy_wf_heuristic_banded_adaptive;
+                attributes.heuristic.min_k = *band_min_k;
+                attributes.heuristic.max_k = *band_max_k;
+                attributes.heuristic.steps_between_cutoffs = *score_steps;
+            }
+            Some(HeuristicStrategy::WFAdaptive {
+                min_wavefront_length,
+                max_distance_threshold,
+                score_steps,
+            }) => {
+                attributes.heuristic.strategy = wfa::wf_heuristic_strategy_wf_heuristic_wfadaptive;
+                attributes.heuristic.min_wavefront_length = *min_wavefront_length;
+                attributes.heuristic.max_distance_threshold = *max_distance_threshold;
+                attributes.heuristic.steps_between_cutoffs = *score_steps;
+            }
+            Some(HeuristicStrategy::XDrop { xdrop, score_steps }) => {
+                attributes.heuristic.strategy = wfa::wf_heuristic_strategy_wf_heuristic_xdrop;
+                attributes.heuristic.xdrop = *xdrop;
+                attributes.heuristic.steps_between_cutoffs = *score_steps;
+            }
+            Some(HeuristicStrategy::ZDrop { zdrop, score_steps }) => {
+                attributes.heuristic.strategy = wfa::wf_heuristic_strategy_wf_heuristic_zdrop;
+                attributes.heuristic.zdrop = *zdrop;
+                attributes.heuristic.steps_between_cutoffs = *score_steps;
+            }
+            Some(HeuristicStrategy::WFMash {
+                min_wavefront_length,
+                max_distance_threshold,
+                score_steps,
+            }) => {
+                attributes.heuristic.strategy = wfa::wf_heuristic_strategy_wf_heuristic_wfmash;
+                attributes.heuristic.min_wavefront_length = *min_wavefront_length;
+                attributes.heuristic.max_distance_threshold = *max_distance_threshold;
+                attributes.heuristic.steps_between_cutoffs = *score_steps;
+            }
+            Some(HeuristicStrategy::None) | _ => {
+                attributes.heuristic.strategy = wfa::wf_heuristic_strategy_wf_heuristic_none;
+            }
+        }
+    }
+
+    /// Update heuristic on an already-created aligner
+    pub fn set_heuristic(&mut self, heuristic: Option<&HeuristicStrategy>) {
+        unsafe {
+            match heuristic {
+                Some(HeuristicStrategy::BandedStatic {
+                    band_min_k,
+                    band_max_k,
+                }) => {
+                    wfa::wavefront_aligner_set_heuristic_banded_static(
+                        self.wf_aligner,
+                        *band_min_k,
+                        *band_max_k,
+                    );
+                }
+                Some(HeuristicStrategy::BandedAdaptive {
+                    band_min_k,
+                    band_max_k,
+                    score_steps,
+                }) => {
+                    wfa::wavefront_aligner_set_heuristic_banded_adaptive(
+                        self.wf_aligner,
+                        *band_min_k,
+                        *band_max_k,
+                        *score_steps,
+                    );
+                }
+                Some(HeuristicStrategy::WFAdaptive {
+                    min_wavefront_length,
+                    max_distance_threshold,
+                    score_steps,
+                }) => {
+                    wfa::wavefront_aligner_set_heuristic_wfadaptive(
+                        self.wf_aligner,
+                        *min_wavefront_length,
+                        *max_distance_threshold,
+                        *score_steps,
+                    );
+                }
+                Some(HeuristicStrategy::XDrop { xdrop, score_steps }) => {
+                    wfa::wavefront_aligner_set_heuristic_xdrop(
+                        self.wf_aligner,
+                        *xdrop,
+                        *score_steps,
+                    );
+                }
+                Some(HeuristicStrategy::ZDrop { zdrop, score_steps }) => {
+                    wfa::wavefront_aligner_set_heuristic_zdrop(
+                        self.wf_aligner,
+                        *zdrop,
+                        *score_steps,
+                    );
+                }
+                Some(HeuristicStrategy::WFMash {
+                    min_wavefront_length,
+                    max_distance_threshold,
+                    score_steps,
+                }) => {
+                    wfa::wavefront_aligner_set_heuristic_wfmash(
+                        self.wf_aligner,
+                        *min_wavefront_length,
+                        *max_distance_threshold,
+                        *score_steps,
+                    );
+                }
+                Some(HeuristicStrategy::None) | None => {
+                    wfa::wavefront_aligner_set_heuristic_none(self.wf_aligner);
+                }
+            }
+        }
+    }
+
+    /// Replaces the aligner's heuristics with all of `strategies` applied together, e.g. a
+    /// static band combined with X-drop.
+    ///
+    /// WFA2-lib keeps the active strategies as a bitmask and each one adds its flag, so they
+    /// can be stacked, but they all write into a single `wavefront_heuristic_t`: the banded
+    /// strategies share the band limits, WF-adaptive and WFMash share their thresholds, and
+    /// every cutoff-based strategy shares `steps_between_cutoffs`. Combinations that would set
+    /// one of these fields to different values are rejected, leaving the aligner unchanged.
+    /// `HeuristicStrategy::None` entries are ignored; an empty slice disables heuristics.
+    pub fn set_heuristics(&mut self, strategies: &[HeuristicStrategy]) -> Result<(), String> {
+        let mut fields: Vec<(&str, std::os::raw::c_int)> = Vec::new();
+        for strategy in strategies {
+            for (name, value) in strategy.heuristic_fields() {
+                match fields.iter().find(|(field, _)| *field == name) {
+                    Some(&(_, previous)) if previous != value => {
+                        return Err(format!(
+                            "Conflicting heuristics: {} set to both {} and {}",
+                            name, previous, value
+                        ));
+                    }
+                    Some(_) => {}
+                    None => fields.push((name, value)),
+                }
+            }
+        }
+
+        self.set_heuristic(None);
+        for strategy in strategies {
+            if !matches!(strategy, HeuristicStrategy::None) {
+                self.set_heuristic(Some(strategy));
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds `strategy` to the aligner's active heuristics, e.g. X-drop on top of a band set at
+    /// construction.
+    ///
+    /// Fails, leaving the aligner unchanged, if it would overwrite a field an active strategy
+    /// uses with a different value (see `set_heuristics`).
+    pub fn add_heuristic(&mut self, strategy: &HeuristicStrategy) -> Result<(), String> {
+        let mut strategies = self.get_heuristics();
+        strategies.push(strategy.clone());
+        self.set_heuristics(&strategies)
+    }
+
+    /// Changes how many score steps pass between heuristic cutoffs (`steps_between_cutoffs`) on
+    /// the live aligner, keeping the rest of the heuristic configuration.
+    ///
+    /// Only the banded-adaptive, WF-adaptive, WFMash, X-drop and Z-drop strategies use this
+    /// value; with no heuristic or a static band it is stored but has no effect.
+    pub fn set_score_steps(&mut self, steps: i32) {
+        let mut heuristic = unsafe { (*self.wf_aligner).heuristic };
+        heuristic.steps_between_cutoffs = steps;
+        self.set_raw_heuristic(heuristic);
+    }
+
+    /// Returns how many score steps pass between heuristic cutoffs.
+    pub fn get_score_steps(&self) -> i32 {
+        unsafe { (*self.wf_aligner).heuristic.steps_between_cutoffs }
+    }
+
+    /// Same as `set_score_steps`, named after the heuristic's `steps_between_cutoffs`.
+    pub fn set_heuristic_steps(&mut self, steps: i32) {
+        self.set_score_steps(steps);
+    }
+
+    /// Same as `get_score_steps`.
+    pub fn get_heuristic_steps(&self) -> i32 {
+        self.get_score_steps()
+    }
+
+    /// Aligns exactly while the score stays within `exact_score_limit`, and only falls back to
+    /// `heuristic` for pairs that go beyond it.
+    ///
+    /// WFA2-lib has no attribute that delays a heuristic until a score threshold is reached:
+    /// `wavefront_heuristic_t` only holds the strategy parameters, `steps_between_cutoffs` and
+    /// internal drop-tracking state. This emulates it with two passes. The first one runs
+    /// without heuristic and is capped at `exact_score_limit` alignment steps; if the cap is hit,
+    /// the pair is realigned with `heuristic`. Near-identical pairs keep their optimal alignment,
+    /// divergent ones pay for one aborted pass before being pruned. The aligner's heuristic and
+    /// step limit are restored afterwards.
+    pub fn align_with_heuristic_after(
+        &mut self,
+        a: &[u8],
+        b: &[u8],
+        heuristic: &HeuristicStrategy,
+        exact_score_limit: i32,
+    ) -> AlignmentStatus {
+        let (saved_heuristic, saved_steps) = unsafe {
+            (
+                (*self.wf_aligner).heuristic,
+                (*self.wf_aligner).system.max_alignment_steps,
+            )
+        };
+
+        self.set_heuristic(None);
+        unsafe {
+            wfa::wavefront_aligner_set_max_alignment_steps(self.wf_aligner, exact_score_limit)
+        };
+        let mut status = self.align(a, b);
+
+        unsafe { wfa::wavefront_aligner_set_max_alignment_steps(self.wf_aligner, saved_steps) };
+        if matches!(status, AlignmentStatus::MaxStepsReached) {
+            self.set_heuristic(Some(heuristic));
+            status = self.align(a, b);
+        }
+
+        self.set_raw_heuristic(saved_heuristic);
+        status
+    }
+
+    /// Aligns in two passes: a coarse one with the current configuration to find the rough path,
+    /// then an exact one restricted to a static band around it.
+    ///
+    /// Set a fast heuristic (e.g. WF-adaptive) before calling this for the coarse pass. The band
+    /// covers the diagonals the coarse path visits, widened by `band_margin` on both sides; if
+    /// the coarse pass does not complete, the band spans just the main and final diagonals plus
+    /// the margin. The refined alignment is exact whenever the optimal path stays inside the band
+    /// (see `path_reached_band_edge`). The aligner's heuristic is restored afterwards.
+    pub fn refine_with_band(&mut self, a: &[u8], b: &[u8], band_margin: i32) -> AlignmentResult {
+        let saved_heuristic = unsafe { (*self.wf_aligner).heuristic };
+        let final_diagonal = b.len() as i32 - a.len() as i32;
+
+        let (mut min_k, mut max_k) = (final_diagonal.min(0), final_diagonal.max(0));
+        if self.align(a, b) == AlignmentStatus::Completed {
+            let (lowest, highest) = diagonal_range(self.cigar());
+            min_k = min_k.min(lowest);
+            max_k = max_k.max(highest);
+        }
+
+        self.set_heuristic(None);
+        self.set_heuristic(Some(&HeuristicStrategy::BandedStatic {
+            band_min_k: min_k - band_margin,
+            band_max_k: max_k + band_margin,
+        }));
+        let status = self.align(a, b);
+        let result = self.result(status);
+
+        self.set_raw_heuristic(saved_heuristic);
+        result
+    }
+
+    /// Overwrites the aligner's heuristic state, keeping the bidirectional aligner in sync.
+    fn set_raw_heuristic(&mut self, mut heuristic: wfa::wavefront_heuristic_t) {
+        unsafe {
+            (*self.wf_aligner).heuristic = heuristic;
+            let bialigner = (*self.wf_aligner).bialigner;
+            if !bialigner.is_null() {
+                wfa::wavefront_bialigner_set_heuristic(bialigner, &mut heuristic);
+            }
+        }
+    }
+
+    pub fn get_heuristics(&self) -> Vec<HeuristicStrategy> {
+        let mut hs = Vec::new();
+        let heuristic = unsafe { *self.wf_aligner }.heuristic;
+        let strategy = heuristic.strategy;
+
+        // WFA2-lib's strategies are distinct bits (1, 2, 4, 16, 32, 64) and can be combined,
+        // e.g. a band with a drop, so every set bit is reported.
+        if strategy & wfa::wf_heuristic_strategy_wf_heuristic_zdrop > 0 {
+            hs.push(HeuristicStrategy::ZDrop {
+                zdrop: heuristic.zdrop,
+                score_steps: heuristic.steps_between_cutoffs,
+            });
+        }
+        if strategy & wfa::wf_heuristic_strategy_wf_heuristic_xdrop > 0 {
+            hs.push(HeuristicStrategy::XDrop {
+                xdrop: heuristic.xdrop,
+                score_steps: heuristic.steps_between_cutoffs,
+            });
+        }
+        if strategy & wfa::wf_heuristic_strategy_wf_heuristic_banded_adaptive > 0 {
+            hs.push(HeuristicStrategy::BandedAdaptive {
+                band_min_k: heuristic.min_k,
+                band_max_k: heuristic.max_k,
+                score_steps: heuristic.steps_between_cutoffs,
+            });
+        }
+        if strategy & wfa::wf_heuristic_strategy_wf_heuristic_banded_static > 0 {
+            hs.push(HeuristicStrategy::BandedStatic {
+                band_min_k: heuristic.min_k,
+                band_max_k: heuristic.max_k,
+            });
+        }
+        if strategy & wfa::wf_heuristic_strategy_wf_heuristic_wfadaptive > 0 {
+            hs.push(HeuristicStrategy::WFAdaptive {
+                min_wavefront_length: heuristic.min_wavefront_length,
+                max_distance_threshold: heuristic.max_distance_threshold,
+                score_steps: heuristic.steps_between_cutoffs,
+            });
+        }
+        if strategy & wfa::wf_heuristic_strategy_wf_heuristic_wfmash > 0 {
+            hs.push(HeuristicStrategy::WFMash {
+                min_wavefront_length: heuristic.min_wavefront_length,
+                max_distance_threshold: heuristic.max_distance_threshold,
+                score_steps: heuristic.steps_between_cutoffs,
+            });
+        }
+        hs
+    }
+
+    pub fn get_alignment_scope(&self) -> AlignmentScope {
+        let a = unsafe { *self.wf_aligner };
+        AlignmentScope::from_scope(a.alignment_scope)
+    }
+
+    /// Switches between score-only (`ComputeScore`) and full (`Alignment`) alignment.
+    ///
+    /// WFA2-lib sizes its internal buffers for the scope when the aligner is created and has no
+    /// setter for it, so the underlying aligner is recreated with the same penalties, heuristic,
+    /// span, memory mode and limits; settings kept on the Rust side are unaffected. In score-only
+    /// mode `cigar()` returns an empty slice and `score()` stays valid. `Undefined` is ignored.
+    ///
+    /// # Panics
+    ///
+    /// If WFA2-lib fails to allocate the new aligner; the current one is then kept.
+    pub fn set_alignment_scope(&mut self, scope: AlignmentScope) {
+        if let Some(value) = scope.to_value() {
+            let mut attributes = self.current_attributes();
+            attributes.alignment_scope = value;
+            self.rebuild(attributes);
+        }
+    }
+
+    /// Returns the symmetric matrix of pairwise scores of `seqs`, e.g. for a phylogeny, with
+    /// each sequence's self-alignment score on the diagonal.
+    ///
+    /// Each unordered pair is aligned once, `seqs[i]` as pattern against `seqs[j]` for `i < j`,
+    /// with `batch_scores`, so a single aligner is reused in score-only mode. With ends-free or
+    /// heuristic alignment, swapping pattern and text can change a score; the matrix keeps the
+    /// `i < j` one on both sides.
+    pub fn all_vs_all_scores(&mut self, seqs: &[&[u8]]) -> Vec<Vec<i32>> {
+        let pairs: Vec<(usize, usize)> = (0..seqs.len())
+            .flat_map(|i| (i..seqs.len()).map(move |j| (i, j)))
+            .collect();
+        let inputs: Vec<(&[u8], &[u8])> = pairs.iter().map(|&(i, j)| (seqs[i], seqs[j])).collect();
+        let scores = self.batch_scores(&inputs);
+
+        let mut matrix = vec![vec![0; seqs.len()]; seqs.len()];
+        for (&(i, j), score) in pairs.iter().zip(scores) {
+            matrix[i][j] = score;
+            matrix[j][i] = score;
+        }
+        matrix
+    }
+
+    /// Aligns every pair with this aligner, returning the `(score, cigar)` of each in order.
+    ///
+    /// WFA2-lib keeps its wavefront buffers between alignments and only grows them when a pair
+    /// needs more, so short pairs reuse the same memory. To keep one long outlier from pinning
+    /// its buffers for the rest of the batch, they are reaped (see `clear`) whenever the aligner
+    /// grows past four times its size at the start. Pairs that do not complete get whatever
+    /// score and CIGAR WFA2-lib reports; check the status with `align` when that matters.
+    pub fn align_batch(&mut self, pairs: &[(&[u8], &[u8])]) -> Vec<(i32, Vec<u8>)> {
+        self.clear();
+        let limit = self.get_aligner_size().saturating_mul(4);
+        pairs
+            .iter()
+            .map(|&(a, b)| {
+                self.align(a, b);
+                let result = (self.score(), self.cigar().to_vec());
+                if self.get_aligner_size() > limit {
+                    self.clear();
+                }
+                result
+            })
+            .collect()
+    }
+
+    /// Scores every pair in score-only mode, returning the scores in order, e.g. to fill a
+    /// distance matrix.
+    ///
+    /// The aligner is switched to score-only once for the whole batch (see
+    /// `set_alignment_scope`) and back afterwards if it was computing full alignments. Pairs
+    /// that do not complete get whatever score WFA2-lib reports; check the status with `align`
+    /// when that matters.
+    pub fn batch_scores(&mut self, pairs: &[(&[u8], &[u8])]) -> Vec<i32> {
+        let saved_scope = self.get_alignment_scope();
+        let switch = !matches!(saved_scope, AlignmentScope::ComputeScore);
+        if switch {
+            self.set_alignment_scope(AlignmentScope::ComputeScore);
+        }
+
+        let scores = pairs
+            .iter()
+            .map(|&(a, b)| {
+                self.align(a, b);
+                self.score()
+            })
+            .collect();
+
+        if switch {
+            self.set_alignment_scope(saved_scope);
+        }
+        scores
+    }
+
+    /// Rebuilds the attributes the aligner was created with from its current state.
+    fn current_attributes(&self) -> wfa::wavefront_aligner_attr_t {
+        unsafe {
+            let mut attributes = wfa::wavefront_aligner_attr_default;
+            Self::set_distance_attr(&mut attributes, &self.get_distance());
+            let aligner = &*self.wf_aligner;
+            attributes.alignment_scope = aligner.alignment_scope;
+            attributes.alignment_form = aligner.alignment_form;
+            attributes.heuristic = aligner.heuristic;
+            attributes.memory_mode = aligner.memory_mode;
+            attributes.system = aligner.system;
+            if !aligner.plot.is_null() {
+                attributes.plot = (*aligner.plot).attributes;
+            }
+            attributes
+        }
+    }
+
+    /// Creates an aligner from `attributes` with the same Rust-side settings as this one.
+    fn with_attributes(&self, mut attributes: wfa::wavefront_aligner_attr_t) -> Self {
+        Self {
+            wf_aligner: unsafe { new_wfa_aligner(&mut attributes) },
+            identical_fast_path: self.identical_fast_path,
+            identical_shortcuts: std::cell::Cell::new(0),
+            gap_preference: self.gap_preference,
+            left_align_indels: self.left_align_indels,
+            max_cigar_len: self.max_cigar_len,
+            min_alignment_length: self.min_alignment_length,
+            label: self.label.clone(),
+            initial_attributes: self.initial_attributes,
+            score_only_copy: std::cell::RefCell::new(None),
+        }
+    }
+
+    /// Replaces the underlying aligner with a new one created from `attributes`.
+    fn rebuild(&mut self, mut attributes: wfa::wavefront_aligner_attr_t) {
+        unsafe {
+            // Creating first keeps the old aligner valid if this panics.
+            let wf_aligner = new_wfa_aligner(&mut attributes);
+            wfa::wavefront_aligner_delete(self.wf_aligner);
+            self.wf_aligner = wf_aligner;
+        }
+        // The penalties, scope or memory mode may have changed.
+        *self.score_only_copy.get_mut() = None;
+    }
+
+    pub fn get_alignment_span(&self) -> AlignmentSpan {
+        let form = unsafe { *self.aligner() }.alignment_form;
+        AlignmentSpan::from_form(form)
+    }
+
+    /// Applies an alignment span to the live aligner; `Undefined` leaves it unchanged.
+    ///
+    /// With `EndsFree`, up to the given number of leading/trailing pattern and text bases can
+    /// be left unaligned at no cost, e.g. free text ends to place a read (pattern) anywhere in
+    /// a longer reference (text) for semi-global mapping.
+    pub fn set_alignment_span(&mut self, span: &AlignmentSpan) {
+        match span {
+            AlignmentSpan::End2End => unsafe {
+                wfa::wavefront_aligner_set_alignment_end_to_end(self.wf_aligner);
+            },
+            AlignmentSpan::EndsFree {
+                pattern_begin_free,
+                pattern_end_free,
+                text_begin_free,
+                text_end_free,
+            } => unsafe {
+                wfa::wavefront_aligner_set_alignment_free_ends(
+                    self.wf_aligner,
+                    *pattern_begin_free,
+                    *pattern_end_free,
+                    *text_begin_free,
+                    *text_end_free,
+                );
+            },
+            AlignmentSpan::Undefined => {}
+        }
+    }
+
+    /// Applies an alignment form (end-to-end, ends-free or extension) to the live aligner.
+    fn set_alignment_form(&mut self, form: &wfa::alignment_form_t) {
+        unsafe {
+            if form.extension {
+                wfa::wavefront_aligner_set_alignment_extension(self.wf_aligner);
+            } else if form.span == wfa::alignment_span_t_alignment_endsfree {
+                wfa::wavefront_aligner_set_alignment_free_ends(
+                    self.wf_aligner,
+                    form.pattern_begin_free,
+                    form.pattern_end_free,
+                    form.text_begin_free,
+                    form.text_end_free,
+                );
+            } else {
+                wfa::wavefront_aligner_set_alignment_end_to_end(self.wf_aligner);
+            }
+        }
+    }
+
+    pub fn get_memory_mode(&self) -> MemoryMode {
+        let a = unsafe { *self.aligner() };
+        MemoryMode::from_value(a.memory_mode)
+    }
+
+    /// Makes every following alignment record a wavefront plot, showing which cells WFA
+    /// explored, e.g. to understand why a heuristic pruned a path. Write it with `write_plot`.
+    ///
+    /// WFA2-lib allocates the plot when the aligner is created, so the underlying aligner is
+    /// recreated as in `set_memory_mode`. The plot grows with the sequences and slows
+    /// alignment down; it is meant for debugging.
+    ///
+    /// # Panics
+    ///
+    /// If WFA2-lib fails to allocate the new aligner; the current one is then kept.
+    pub fn enable_plot(&mut self, params: PlotParams) {
+        let mut attributes = self.current_attributes();
+        attributes.plot.enabled = true;
+        attributes.plot.resolution_points = params.resolution_points;
+        attributes.plot.align_level = params.align_level;
+        self.rebuild(attributes);
+    }
+
+    /// Writes the wavefront plot of the last alignment to `path` in WFA2-lib's plot format
+    /// (as read by its `plot` scripts). Fails if plotting was not enabled with `enable_plot`.
+    pub fn write_plot(&self, path: &std::path::Path) -> std::io::Result<()> {
+        let invalid =
+            |message: &str| std::io::Error::new(std::io::ErrorKind::InvalidInput, message);
+        if unsafe { (*self.wf_aligner).plot.is_null() } {
+            return Err(invalid("plotting is not enabled on this aligner"));
+        }
+        let path = path
+            .to_str()
+            .and_then(|path| std::ffi::CString::new(path).ok())
+            .ok_or_else(|| invalid("plot path is not a valid C string"))?;
+        unsafe {
+            let stream = fopen(path.as_ptr(), c"w".as_ptr());
+            if stream.is_null() {
+                return Err(std::io::Error::last_os_error());
+            }
+            wfa::wavefront_plot_print(stream, self.wf_aligner);
+            if fclose(stream) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the memory mode, e.g. `Low` or `Ultralow` to align very long sequences in less
+    /// memory at some cost in speed. `Undefined` is ignored.
+    ///
+    /// WFA2-lib picks its wavefront allocation and backtrace machinery from the mode when the
+    /// aligner is created; ultralow in particular runs the bidirectional (BiWFA) aligner, which
+    /// only exists if it was requested at creation. Writing the field of a live aligner leaves
+    /// this machinery inconsistent with the mode, which is what broke ultralow, so the
+    /// underlying aligner is recreated with the same penalties, heuristic, scope, span and
+    /// limits instead. Settings kept on the Rust side are unaffected.
+    ///
+    /// # Panics
+    ///
+    /// If WFA2-lib fails to allocate the new aligner; the current one is then kept.
+    pub fn set_memory_mode(&mut self, mode: MemoryMode) {
+        if let Some(value) = mode.to_value() {
+            let mut attributes = self.current_attributes();
+            attributes.memory_mode = value;
+            self.rebuild(attributes);
+        }
+    }
+}
+
+/// Aligns queries against a position-specific scoring profile instead of a reference sequence.
+///
+/// The profile plays the text role: column `h` holds the scores of `A`, `C`, `G` and `T` at
+/// text position `h`, so the CIGAR and coordinates refer to profile columns. WFA2-lib's custom
+/// match function can only answer whether a pattern base matches a text position, not score
+/// it, so the profile is thresholded: a query base matches a column when its score is at least
+/// `min_match_score`, and everything else is a mismatch with the aligner's regular penalties.
+/// Bases other than `ACGT` (case-insensitive) never match. The wrapped aligner must not use the
+/// ultralow (bidirectional) memory mode, which WFA2-lib does not support with custom matching.
+pub struct ProfileAligner {
+    aligner: AffineWavefronts,
+    profile: Vec<[i32; 4]>,
+    min_match_score: i32,
+}
+
+/// Arguments handed to `profile_match` through WFA2-lib's opaque callback pointer.
+struct ProfileMatch<'a> {
+    query: &'a [u8],
+    profile: &'a [[i32; 4]],
+    min_match_score: i32,
+}
+
+unsafe extern "C" fn profile_match(
+    v: std::os::raw::c_int,
+    h: std::os::raw::c_int,
+    arguments: *mut std::os::raw::c_void,
+) -> std::os::raw::c_int {
+    let arguments = &*(arguments as *const ProfileMatch);
+    // WFA2-lib probes past the sequence ends while extending, so bounds are checked here.
+    let (Some(&base), Some(column)) = (
+        arguments.query.get(v as usize),
+        arguments.profile.get(h as usize),
+    ) else {
+        return 0;
+    };
+    let index = match base.to_ascii_uppercase() {
+        b'A' => 0,
+        b'C' => 1,
+        b'G' => 2,
+        b'T' => 3,
+        _ => return 0,
+    };
+    (column[index] >= arguments.min_match_score) as std::os::raw::c_int
+}
+
+impl ProfileAligner {
+    pub fn new(aligner: AffineWavefronts, profile: Vec<[i32; 4]>, min_match_score: i32) -> Self {
+        Self {
+            aligner,
+            profile,
+            min_match_score,
+        }
+    }
+
+    /// Aligns `query` (pattern) against the profile (text).
+    ///
+    /// Length limits, empty queries or profiles, the gap preference and the minimum alignment
+    /// length are handled as in `AffineWavefronts::align`. Indels are not left-aligned, as the
+    /// profile has no bases to compare.
+    pub fn align(&self, query: &[u8]) -> AlignmentStatus {
+        if let Some(status) = self.aligner.check_lengths(query.len(), self.profile.len()) {
+            return status;
+        }
+        let mut arguments = ProfileMatch {
+            query,
+            profile: &self.profile,
+            min_match_score: self.min_match_score,
+        };
+        let status: AlignmentStatus = unsafe {
+            wfa::wavefront_align_lambda(
+                self.aligner.wf_aligner,
+                Some(profile_match),
+                &mut arguments as *mut ProfileMatch as *mut std::os::raw::c_void,
+                query.len() as i32,
+                self.profile.len() as i32,
+            )
+        }
+        .into();
+        self.aligner.finish_alignment(status, None)
+    }
+
+    pub fn profile(&self) -> &[[i32; 4]] {
+        &self.profile
+    }
+
+    /// Returns the wrapped aligner, e.g. to read the CIGAR and score of the last alignment.
+    pub fn aligner(&self) -> &AffineWavefronts {
+        &self.aligner
+    }
+}
+
+/// Scores the candidate `pairs` of `seqs` (e.g. those passing a k-mer prefilter) with a single
+/// score-only aligner built from `config`, returning the `(i, j, score)` edges of a sparse
+/// similarity graph.
+///
+/// Only completed alignments at least as good as `min_score` are kept: `score >= min_score`
+/// for the gap-affine metrics, whose scores are negated penalties, and `score <= min_score` for
+/// edit distance. Edges are returned in the order of `pairs`.
+///
+/// Panics if a pair refers to an index outside `seqs`.
+pub fn sparse_scores(
+    config: &AlignerConfig,
+    seqs: &[&[u8]],
+    pairs: &[(usize, usize)],
+    min_score: i32,
+) -> Vec<(usize, usize, i32)> {
+    let aligner = config.create_score_only_aligner();
+    let is_edit = matches!(config.distance, Distance::Edit);
+
+    pairs
+        .iter()
+        .filter_map(|&(i, j)| {
+            let status = aligner.align(seqs[i], seqs[j]);
+            let score = aligner.score();
+            let kept = if is_edit {
+                score <= min_score
+            } else {
+                score >= min_score
+            };
+            (status == AlignmentStatus::Completed && kept).then_some((i, j, score))
+        })
+        .collect()
+}
+
+/// Returns the Levenshtein (edit) distance between `a` and `b`.
+///
+/// Each thread keeps its own score-only edit aligner, without heuristic, so repeated calls
+/// do not recreate it. Panics if the alignment does not complete, e.g. for sequences longer
+/// than `i32::MAX` bases.
+pub fn edit_distance(a: &[u8], b: &[u8]) -> u32 {
+    thread_local! {
+        static ALIGNER: AffineWavefronts = AffineWavefrontsBuilder::new()
+            .distance(Distance::Edit)
+            .heuristic(HeuristicStrategy::None)
+            .scope(AlignmentScope::ComputeScore)
+            .build();
+    }
+    ALIGNER.with(|aligner| {
+        let status = aligner.align(a, b);
+        assert!(
+            status.is_completed(),
+            "Edit distance alignment did not complete: {:?}",
+            status
+        );
+        aligner.score().unsigned_abs()
+    })
+}
+
+/// Shifts every gap of `cigar` as far left as possible while keeping the same bases matched,
+/// with `pattern` and `text` the aligned sequences.
+///
+/// A gap moves left past a match whenever the base it uncovers equals the last base of the
+/// gap, i.e. within repeats of the gapped sequence. The score is unchanged.
+pub fn left_align_indels(cigar: &mut [u8], pattern: &[u8], text: &[u8]) {
+    let (mut column, mut i, mut j) = (0, 0, 0);
+    while column < cigar.len() {
+        let op = cigar[column];
+        let length = cigar[column..].iter().take_while(|&&o| o == op).count();
+        let (gapped, start) = match op {
+            b'I' => (text, j),
+            b'D' => (pattern, i),
+            _ => {
+                if op == b'M' || op == b'X' {
+                    i += length;
+                    j += length;
+                }
+                column += length;
+                continue;
+            }
+        };
+
+        // Move the match before the gap to its end while the shifted gap keeps the bases.
+        let (mut gap_column, mut gap_start) = (column, start);
+        while gap_column > 0
+            && cigar[gap_column - 1] == b'M'
+            && gapped[gap_start - 1] == gapped[gap_start + length - 1]
+        {
+            cigar[gap_column - 1..gap_column + length].rotate_left(1);
+            gap_column -= 1;
+            gap_start -= 1;
+        }
+
+        match op {
+            b'I' => j += length,
+            _ => i += length,
+        }
+        column += length;
+    }
+}
+
+/// Returns the operations of a WFA2-lib CIGAR as a mutable slice.
+///
+/// # Safety
+///
+/// `cigar` must point to a valid CIGAR that is not otherwise borrowed for the returned lifetime.
+unsafe fn cigar_operations_mut<'a>(cigar: *mut wfa::cigar_t) -> &'a mut [u8] {
+    let length = ((*cigar).end_offset - (*cigar).begin_offset).max(0) as usize;
+    slice::from_raw_parts_mut(
+        (*cigar).operations.add((*cigar).begin_offset as usize) as *mut u8,
+        length,
+    )
+}
+
+/// Minimum amount of work, in aligned bases, worth handing to a thread of its own.
+const MIN_BASES_PER_THREAD: usize = 1 << 16;
+
+/// Recommends how many threads to use for aligning `num_pairs` pairs of about `avg_seq_len`
+/// bases each, e.g. when a caller asks for an automatic thread count.
+///
+/// Each thread gets at least `MIN_BASES_PER_THREAD` bases (64 Ki) and at least one pair, so
+/// small batches stay on one thread and the per-thread setup (creating an aligner) is amortized;
+/// longer sequences reach that amount with fewer pairs. The result is at least 1 and at most
+/// `std::thread::available_parallelism()`.
+pub fn recommended_threads(num_pairs: usize, avg_seq_len: usize) -> usize {
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let work = num_pairs.saturating_mul(avg_seq_len.max(1));
+    (work / MIN_BASES_PER_THREAD).min(num_pairs).clamp(1, cores)
+}
+
+/// Chains colinear pieces of an alignment of the same query, e.g. from split alignment, into
+/// spanning blocks.
+///
+/// Blocks are taken in query order. A block is merged into the previous one when it starts at
+/// or after its end on both the query and the reference, at most `max_gap` bases later on
+/// each; the bases in between are bridged with `D` (query-only) and `I` (reference-only)
+/// operations, following WFA2-lib's convention. Overlapping or out-of-order blocks are kept
+/// apart.
+pub fn merge_colinear(alignments: &[AlignedBlock], max_gap: usize) -> Vec<AlignedBlock> {
+    let mut blocks = alignments.to_vec();
+    blocks.sort_by_key(|block| (block.query_range.start, block.reference_range.start));
+
+    let mut merged: Vec<AlignedBlock> = Vec::with_capacity(blocks.len());
+    for block in blocks {
+        if let Some(last) = merged.last_mut() {
+            let colinear = block.query_range.start >= last.query_range.end
+                && block.reference_range.start >= last.reference_range.end;
+            let (query_gap, reference_gap) = last.gap_to(&block);
+            if colinear && query_gap <= max_gap && reference_gap <= max_gap {
+                last.cigar.extend(std::iter::repeat_n(b'D', query_gap));
+                last.cigar.extend(std::iter::repeat_n(b'I', reference_gap));
+                last.cigar.extend_from_slice(&block.cigar);
+                last.query_range.end = block.query_range.end;
+                last.reference_range.end = block.reference_range.end;
+                continue;
+            }
+        }
+        merged.push(block);
+    }
+    merged
+}
+
+/// Aligns reads against a consensus whose bases carry a confidence (Phred quality), so that
+/// disagreeing with an uncertain consensus base is not penalized, e.g. in polishing loops.
+///
+/// WFA2-lib's custom match function only answers whether two bases match, so the mismatch
+/// penalty can only be scaled by 0 or 1: a read base mismatching a consensus base costs the
+/// aligner's full mismatch penalty when the base's quality is at least `min_quality`, and
+/// nothing otherwise (the position matches any base). Gaps are penalized as usual. Base
+/// comparison is case-insensitive. As with `ProfileAligner`, the ultralow memory mode is not
+/// supported.
+pub struct ConsensusAligner {
+    aligner: AffineWavefronts,
+    consensus: Vec<u8>,
+    qualities: Vec<u8>,
+    min_quality: u8,
+}
+
+/// Arguments handed to `consensus_match` through WFA2-lib's opaque callback pointer.
+struct ConsensusMatch<'a> {
+    read: &'a [u8],
+    consensus: &'a [u8],
+    qualities: &'a [u8],
+    min_quality: u8,
+}
+
+unsafe extern "C" fn consensus_match(
+    v: std::os::raw::c_int,
+    h: std::os::raw::c_int,
+    arguments: *mut std::os::raw::c_void,
+) -> std::os::raw::c_int {
+    let arguments = &*(arguments as *const ConsensusMatch);
+    // WFA2-lib probes past the sequence ends while extending, so bounds are checked here.
+    let (Some(base), Some(consensus)) = (
+        arguments.read.get(v as usize),
+        arguments.consensus.get(h as usize),
+    ) else {
+        return 0;
+    };
+    let uncertain = arguments.qualities[h as usize] < arguments.min_quality;
+    (uncertain || base.eq_ignore_ascii_case(consensus)) as std::os::raw::c_int
+}
+
+impl ConsensusAligner {
+    /// Wraps `aligner` to align against `consensus`, with one quality per consensus base.
+    pub fn new(
+        aligner: AffineWavefronts,
+        consensus: Vec<u8>,
+        qualities: Vec<u8>,
+        min_quality: u8,
+    ) -> Result<Self, String> {
+        if consensus.len() != qualities.len() {
+            return Err(format!(
+                "Consensus has {} bases but {} qualities",
+                consensus.len(),
+                qualities.len()
+            ));
+        }
+        Ok(Self {
+            aligner,
+            consensus,
+            qualities,
+            min_quality,
+        })
+    }
+
+    /// Aligns `read` (pattern) against the consensus (text).
+    ///
+    /// Length limits, empty reads or consensus, the gap preference and the minimum alignment
+    /// length are handled as in `AffineWavefronts::align`. Indels are not left-aligned: a
+    /// shifted gap can move a match onto a consensus base of different quality.
+    pub fn align(&self, read: &[u8]) -> AlignmentStatus {
+        if let Some(status) = self.aligner.check_lengths(read.len(), self.consensus.len()) {
+            return status;
+        }
+        let mut arguments = ConsensusMatch {
+            read,
+            consensus: &self.consensus,
+            qualities: &self.qualities,
+            min_quality: self.min_quality,
+        };
+        let status: AlignmentStatus = unsafe {
+            wfa::wavefront_align_lambda(
+                self.aligner.wf_aligner,
+                Some(consensus_match),
+                &mut arguments as *mut ConsensusMatch as *mut std::os::raw::c_void,
+                read.len() as i32,
+                self.consensus.len() as i32,
+            )
+        }
+        .into();
+        self.aligner.finish_alignment(status, None)
+    }
+
+    pub fn consensus(&self) -> &[u8] {
+        &self.consensus
+    }
+
+    /// Returns the wrapped aligner, e.g. to read the CIGAR and score of the last alignment.
+    pub fn aligner(&self) -> &AffineWavefronts {
+        &self.aligner
+    }
+}
+
+/// Aligns with some substitutions counted as matches, e.g. bisulfite-converted reads where a
+/// read `T` over a reference `C` is not a mismatch.
+///
+/// WFA2-lib penalizes every mismatch with the same single penalty and has no substitution
+/// matrix, so arbitrary per-pair costs are not possible. What it does support is a custom match
+/// function, which this uses: a `(pattern, text)` base pair is either free, like a match, or a
+/// mismatch with the aligner's regular penalty. Identical bases always match and comparison is
+/// case-insensitive. As with `ProfileAligner`, the ultralow memory mode is not supported.
+pub struct SubstitutionAligner {
+    aligner: AffineWavefronts,
+    /// `free[p][t]`: whether pattern base `p` matches text base `t`, both uppercase.
+    free: Box<[[bool; 256]; 256]>,
+}
+
+/// Arguments handed to `substitution_match` through WFA2-lib's opaque callback pointer.
+struct SubstitutionMatch<'a> {
+    pattern: &'a [u8],
+    text: &'a [u8],
+    free: &'a [[bool; 256]; 256],
+}
+
+unsafe extern "C" fn substitution_match(
+    v: std::os::raw::c_int,
+    h: std::os::raw::c_int,
+    arguments: *mut std::os::raw::c_void,
+) -> std::os::raw::c_int {
+    let arguments = &*(arguments as *const SubstitutionMatch);
+    // WFA2-lib probes past the sequence ends while extending, so bounds are checked here.
+    let (Some(p), Some(t)) = (
+        arguments.pattern.get(v as usize),
+        arguments.text.get(h as usize),
+    ) else {
+        return 0;
+    };
+    arguments.free[p.to_ascii_uppercase() as usize][t.to_ascii_uppercase() as usize]
+        as std::os::raw::c_int
+}
+
+impl SubstitutionAligner {
+    /// Wraps `aligner` so that each `(pattern_base, text_base)` of `free_substitutions` counts
+    /// as a match (in that direction only; add the reverse pair for a symmetric rule).
+    pub fn new(aligner: AffineWavefronts, free_substitutions: &[(u8, u8)]) -> Self {
+        let mut free = Box::new([[false; 256]; 256]);
+        for base in 0..=255u8 {
+            free[base.to_ascii_uppercase() as usize][base.to_ascii_uppercase() as usize] = true;
+        }
+        for &(pattern_base, text_base) in free_substitutions {
+            free[pattern_base.to_ascii_uppercase() as usize]
+                [text_base.to_ascii_uppercase() as usize] = true;
+        }
+        Self { aligner, free }
+    }
+
+    /// Aligns `pattern` against `text`. Free substitutions show up as matches (`M`) in the CIGAR.
+    ///
+    /// Length limits, empty sequences and the other Rust-side settings of the wrapped aligner
+    /// (indel left-alignment, gap preference, minimum alignment length) are handled as in
+    /// `AffineWavefronts::align`.
+    pub fn align(&self, pattern: &[u8], text: &[u8]) -> AlignmentStatus {
+        if let Some(status) = self.aligner.check_lengths(pattern.len(), text.len()) {
+            return status;
+        }
+        let mut arguments = SubstitutionMatch {
+            pattern,
+            text,
+            free: &self.free,
+        };
+        let status: AlignmentStatus = unsafe {
+            wfa::wavefront_align_lambda(
+                self.aligner.wf_aligner,
+                Some(substitution_match),
+                &mut arguments as *mut SubstitutionMatch as *mut std::os::raw::c_void,
+                pattern.len() as i32,
+                text.len() as i32,
+            )
+        }
+        .into();
+        self.aligner.finish_alignment(status, Some((pattern, text)))
+    }
+
+    /// Returns the wrapped aligner, e.g. to read the CIGAR and score of the last alignment.
+    pub fn aligner(&self) -> &AffineWavefronts {
+        &self.aligner
+    }
+}
+
+/// Returns the lengths of the leading and trailing lowercase (soft-masked) runs of `seq`.
+///
+/// Interior lowercase bases are not counted. An all-lowercase sequence is reported as a single
+/// leading run.
+pub fn soft_masked_ends(seq: &[u8]) -> (usize, usize) {
+    let head = seq.iter().take_while(|b| b.is_ascii_lowercase()).count();
+    let tail = seq[head..]
+        .iter()
+        .rev()
+        .take_while(|b| b.is_ascii_lowercase())
+        .count();
+    (head, tail)
+}
+
+/// Returns the run of a CIGAR covering `column`, if the CIGAR is long enough.
+fn run_at(cigar: &[u8], column: usize) -> Option<(CigarOp, u32)> {
+    let mut end = 0;
+    CigarOps::new(cigar).find(|&(_, length)| {
+        end += length as usize;
+        end > column
+    })
+}
+
+/// Returns the reverse complement of a DNA sequence, keeping the case of each base. Bases other
+/// than `ACGT` are kept as they are.
+fn reverse_complement(seq: &[u8]) -> Vec<u8> {
+    seq.iter()
+        .rev()
+        .map(|&base| match base {
+            b'A' => b'T',
+            b'C' => b'G',
+            b'G' => b'C',
+            b'T' => b'A',
+            b'a' => b't',
+            b'c' => b'g',
+            b'g' => b'c',
+            b't' => b'a',
+            other => other,
+        })
+        .collect()
+}
+
+/// Run-length encodes a CIGAR with SAM operations, as in `sam_cigar`. An empty CIGAR gives an
+/// empty list.
+fn sam_cigar_runs(cigar: &[u8], pattern_is_reference: bool, extended: bool) -> Vec<(u8, u32)> {
+    let mut runs: Vec<(u8, u32)> = Vec::new();
+    for (op, length) in CigarOps::new(cigar) {
+        let op = match (op, pattern_is_reference) {
+            (CigarOp::Match, _) if extended => b'=',
+            (CigarOp::Mismatch, _) if extended => b'X',
+            (CigarOp::Match | CigarOp::Mismatch, _) => b'M',
+            (CigarOp::Insertion, false) => b'D',
+            (CigarOp::Deletion, false) => b'I',
+            (op, true) => op.to_byte(),
+        };
+        match runs.last_mut() {
+            Some((last, total)) if *last == op => *total += length,
+            _ => runs.push((op, length)),
+        }
+    }
+    runs
+}
+
+/// Run-length encodes a CIGAR with SAM operations: `M`/`X` are merged into `M`, or written as
+/// `=`/`X` if `extended`. With the pattern as the reference, `I`/`D` keep their letters; with
+/// the pattern as the query (the usual read-against-reference case) they are swapped. An empty
+/// CIGAR gives `*`.
+fn sam_cigar(cigar: &[u8], pattern_is_reference: bool, extended: bool) -> String {
+    let runs = sam_cigar_runs(cigar, pattern_is_reference, extended);
+    if runs.is_empty() {
+        return "*".to_string();
+    }
+    runs.iter()
+        .map(|&(op, length)| format!("{}{}", length, op as char))
+        .collect()
+}
+
+/// Aligns `pattern` against `text` end-to-end with a dense Gotoh matrix, returning the CIGAR
+/// and its penalty. Only meant for tiny sequences: memory grows with the product of lengths.
+fn dense_alignment(pattern: &[u8], text: &[u8], distance: &Distance) -> (Vec<u8>, i32) {
+    const INF: i32 = i32::MAX / 2;
+    let (n, m) = (pattern.len(), text.len());
+    let width = m + 1;
+    let at = |i: usize, j: usize| i * width + j;
+    let models = distance.gap_models();
+    let mismatch = distance.mismatch_penalty();
+    let substitution = |i: usize, j: usize| {
+        if pattern[i - 1] == text[j - 1] {
+            0
+        } else {
+            mismatch
+        }
+    };
+
+    // Best penalty ending at (i, j) in any state, and per gap model ending in an `I` (text
+    // only) or a `D` (pattern only) operation.
+    let mut best = vec![INF; (n + 1) * width];
+    let mut ins = vec![vec![INF; (n + 1) * width]; models.len()];
+    let mut del = vec![vec![INF; (n + 1) * width]; models.len()];
+    best[at(0, 0)] = 0;
+    for i in 0..=n {
+        for j in 0..=m {
+            if i == 0 && j == 0 {
+                continue;
+            }
+            let mut cell = INF;
+            if i > 0 && j > 0 {
+                cell = best[at(i - 1, j - 1)] + substitution(i, j);
+            }
+            for (k, &(opening, extension)) in models.iter().enumerate() {
+                if j > 0 {
+                    let open = best[at(i, j - 1)] + opening + extension;
+                    ins[k][at(i, j)] = open.min(ins[k][at(i, j - 1)] + extension);
+                }
+                if i > 0 {
+                    let open = best[at(i - 1, j)] + opening + extension;
+                    del[k][at(i, j)] = open.min(del[k][at(i - 1, j)] + extension);
+                }
+                cell = cell.min(ins[k][at(i, j)]).min(del[k][at(i, j)]);
+            }
+            best[at(i, j)] = cell;
+        }
+    }
+
+    // Traceback, preferring substitutions over gaps and gap openings over extensions.
+    enum State {
+        Best,
+        Ins(usize),
+        Del(usize),
+    }
+    let mut cigar = Vec::with_capacity(n + m);
+    let (mut i, mut j) = (n, m);
+    let mut state = State::Best;
+    while i > 0 || j > 0 {
+        match state {
+            State::Best => {
+                let value = best[at(i, j)];
+                if i > 0 && j > 0 && value == best[at(i - 1, j - 1)] + substitution(i, j) {
+                    cigar.push(if substitution(i, j) == 0 { b'M' } else { b'X' });
+                    i -= 1;
+                    j -= 1;
+                } else if let Some(k) = (0..models.len()).find(|&k| ins[k][at(i, j)] == value) {
+                    state = State::Ins(k);
+                } else {
+                    let k = (0..models.len())
+                        .find(|&k| del[k][at(i, j)] == value)
+                        .expect("every cell comes from a substitution or a gap");
+                    state = State::Del(k);
+                }
+            }
+            State::Ins(k) => {
+                let (opening, extension) = models[k];
+                cigar.push(b'I');
+                if ins[k][at(i, j)] == best[at(i, j - 1)] + opening + extension {
+                    state = State::Best;
+                }
+                j -= 1;
+            }
+            State::Del(k) => {
+                let (opening, extension) = models[k];
+                cigar.push(b'D');
+                if del[k][at(i, j)] == best[at(i - 1, j)] + opening + extension {
+                    state = State::Best;
+                }
+                i -= 1;
+            }
+        }
+    }
+    cigar.reverse();
+    (cigar, best[at(n, m)])
+}
+
+/// Returns the number of CIGAR columns from the first to the last match or mismatch.
+fn aligned_block_length(cigar: &[u8]) -> usize {
+    let is_aligned = |op: &u8| *op == b'M' || *op == b'X';
+    match (
+        cigar.iter().position(is_aligned),
+        cigar.iter().rposition(is_aligned),
+    ) {
+        (Some(first), Some(last)) => last - first + 1,
+        _ => 0,
+    }
+}
+
+/// Returns the lowest and highest diagonals (text minus pattern position) a CIGAR's path
+/// visits, starting from diagonal 0.
+fn diagonal_range(cigar: &[u8]) -> (i32, i32) {
+    let mut k = 0;
+    let (mut lowest, mut highest) = (0, 0);
+    for &op in cigar {
+        match op {
+            b'I' => k += 1,
+            b'D' => k -= 1,
+            _ => continue,
+        }
+        lowest = lowest.min(k);
+        highest = highest.max(k);
+    }
+    (lowest, highest)
+}
+
+/// Returns the half-open interval of text positions covered by a CIGAR, leaving out its
+/// leading and trailing text-only operations.
+fn coverage_interval(cigar: &[u8]) -> (usize, usize) {
+    let leading = cigar.iter().take_while(|&&op| op == b'I' || op == b'D');
+    let start = leading.filter(|&&op| op == b'I').count();
+    let covered = match cigar.iter().rposition(|&op| op == b'M' || op == b'X') {
+        Some(last) => cigar_spans(&cigar[..=last]).1 - start,
+        None => 0,
+    };
+    (start, start + covered)
+}
+
+/// `length` as a WFA2-lib free-end length, saturating instead of wrapping past `i32::MAX`, so
+/// that `align` still sees the real sequences and rejects them as `TooLong`.
+fn free_end_length(length: usize) -> i32 {
+    i32::try_from(length).unwrap_or(i32::MAX)
+}
+
+/// Returns how many `(pattern, text)` bases a CIGAR consumes.
+fn cigar_spans(cigar: &[u8]) -> (usize, usize) {
+    cigar.iter().fold((0, 0), |(i, j), &op| match op {
+        b'M' | b'X' => (i + 1, j + 1),
+        b'I' => (i, j + 1),
+        b'D' => (i + 1, j),
+        _ => (i, j),
+    })
+}
+
+/// Walks a CIGAR column by column, yielding each operation with the pattern and text positions
+/// it starts at. `M`/`X` consume both sequences, `I` only the text and `D` only the pattern.
+fn cigar_columns(cigar: &[u8]) -> impl Iterator<Item = (u8, usize, usize)> + '_ {
+    cigar.iter().scan((0, 0), |(i, j), &op| {
+        let column = (op, *i, *j);
+        match op {
+            b'M' | b'X' => {
+                *i += 1;
+                *j += 1;
+            }
+            b'I' => *j += 1,
+            b'D' => *i += 1,
+            _ => {}
+        }
+        Some(column)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GAP_AFFINE: Distance = Distance::GapAffine {
+        mismatch: 4,
+        gap_opening: 6,
+        gap_extension: 2,
+    };
+
+    fn gap_affine() -> AffineWavefronts {
+        GAP_AFFINE.create_aligner(None)
+    }
+
+    /// Deterministic pseudo-random DNA of `length` bases.
+    fn random_sequence(length: usize, seed: u32) -> Vec<u8> {
+        let mut state = seed;
+        (0..length)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                b"ACGT"[(state >> 16) as usize % 4]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn null_aligner_is_an_error() {
+        // Memory limits only apply while aligning, so allocation failure cannot be forced
+        // through the public API; feed the null WFA2-lib would return instead.
+        let error = AffineWavefronts::try_from_aligner(std::ptr::null_mut()).unwrap_err();
+        assert_eq!(error, ALLOCATION_FAILED);
+    }
+
+    #[test]
+    fn fallible_constructors_succeed() {
+        assert!(Distance::Edit.try_create_aligner(None).is_ok());
+        assert!(AlignerConfig::short_read_preset()
+            .try_create_aligner()
+            .is_ok());
+        assert!(AffineWavefrontsBuilder::new().try_build().is_ok());
+        assert!(AffineWavefronts::try_new_edit().is_ok());
+    }
+
+    #[test]
+    fn profile_aligner_matches_columns() {
+        let profile = vec![[5, 0, 0, 0], [0, 5, 0, 0]];
+        let aligner = ProfileAligner::new(gap_affine(), profile, 1);
+        assert_eq!(aligner.align(b"AC"), AlignmentStatus::Completed);
+        assert_eq!(aligner.aligner().cigar(), b"MM");
+        assert_eq!(aligner.aligner().score(), 0);
+    }
+
+    #[test]
+    fn profile_aligner_handles_empty_query() {
+        let profile = vec![[5, 0, 0, 0], [0, 5, 0, 0]];
+        let aligner = ProfileAligner::new(gap_affine(), profile, 1);
+        assert_eq!(aligner.align(b""), AlignmentStatus::Completed);
+        assert_eq!(aligner.aligner().cigar(), b"II");
+        assert_eq!(aligner.aligner().score(), -10);
+    }
+
+    #[test]
+    fn consensus_aligner_forgives_uncertain_mismatches() {
+        let aligner =
+            ConsensusAligner::new(gap_affine(), b"ACGT".to_vec(), vec![30, 30, 5, 30], 20).unwrap();
+        assert_eq!(aligner.align(b"ACTT"), AlignmentStatus::Completed);
+        let uncertain = aligner.aligner().score();
+        assert_eq!(aligner.align(b"TCGT"), AlignmentStatus::Completed);
+        let certain = aligner.aligner().score();
+        assert!(uncertain > certain);
+    }
+
+    #[test]
+    fn consensus_aligner_handles_empty_read() {
+        let aligner =
+            ConsensusAligner::new(gap_affine(), b"ACG".to_vec(), vec![30; 3], 20).unwrap();
+        assert_eq!(aligner.align(b""), AlignmentStatus::Completed);
+        assert_eq!(aligner.aligner().cigar(), b"III");
+        assert_eq!(aligner.aligner().score(), -12);
+    }
+
+    #[test]
+    fn substitution_aligner_frees_listed_pairs() {
+        let aligner = SubstitutionAligner::new(gap_affine(), &[(b'T', b'C')]);
+        assert_eq!(aligner.align(b"ATGT", b"ACGT"), AlignmentStatus::Completed);
+        assert_eq!(aligner.aligner().cigar(), b"MMMM");
+        assert_eq!(aligner.aligner().score(), 0);
+        // Only in the listed direction.
+        assert_eq!(aligner.align(b"ACGT", b"ATGT"), AlignmentStatus::Completed);
+        assert_eq!(aligner.aligner().cigar(), b"MXMM");
+        assert_eq!(aligner.aligner().score(), -4);
+    }
+
+    #[test]
+    fn substitution_aligner_handles_empty_sequences() {
+        let aligner = SubstitutionAligner::new(gap_affine(), &[(b'T', b'C')]);
+        assert_eq!(aligner.align(b"AC", b""), AlignmentStatus::Completed);
+        assert_eq!(aligner.aligner().cigar(), b"DD");
+        assert_eq!(aligner.aligner().score(), -10);
+    }
+
+    #[test]
+    fn score_only_matches_align() {
+        let aligner = gap_affine();
+        let pairs: [(&[u8], &[u8]); 5] = [
+            (b"ACGT", b"AGT"),
+            (b"ACGTACGT", b"ACCTACGTT"),
+            (b"ACGT", b"ACGT"),
+            (b"", b"ACG"),
+            (b"", b""),
+        ];
+        for (a, b) in pairs {
+            assert_eq!(aligner.align(a, b), AlignmentStatus::Completed);
+            assert_eq!(aligner.score_only(a, b), Ok(aligner.score()));
+        }
+    }
+
+    #[test]
+    fn score_only_follows_the_configuration() {
+        let mut aligner = gap_affine();
+        assert_eq!(aligner.score_only(b"ACGT", b"TTACGTTT"), Ok(-20));
+
+        // The cached score-only copy picks up the new span...
+        aligner.set_alignment_span(&ends_free(2, 2));
+        assert_eq!(aligner.score_only(b"ACGT", b"TTACGTTT"), Ok(0));
+
+        // ...and is recreated with the aligner.
+        aligner.set_alignment_scope(AlignmentScope::Alignment);
+        assert_eq!(aligner.score_only(b"ACGT", b"TTACGTTT"), Ok(0));
+        aligner.reset();
+        assert_eq!(aligner.score_only(b"ACGT", b"TTACGTTT"), Ok(-20));
+    }
+
+    #[test]
+    fn score_only_keeps_the_aligner() {
+        let aligner = gap_affine();
+        assert_eq!(aligner.align(b"ACGT", b"AGT"), AlignmentStatus::Completed);
+        assert_eq!(aligner.score_only(b"", b"ACG"), Ok(-12));
+        assert_eq!(aligner.score_only(b"ACGT", b"ACGT"), Ok(0));
+        assert!(matches!(
+            aligner.get_alignment_scope(),
+            AlignmentScope::Alignment
+        ));
+        assert_eq!(aligner.cigar(), b"MDMM");
+
+        let mut scorer = gap_affine();
+        scorer.set_alignment_scope(AlignmentScope::ComputeScore);
+        assert_eq!(scorer.score_only(b"ACGT", b"AGT"), Ok(-8));
+        assert_eq!(scorer.score_only(b"ACG", b""), Ok(-12));
+    }
+
+    #[test]
+    fn identical_shortcut_matches_wfa() {
+        for distance in [Distance::Edit, GAP_AFFINE] {
+            let shortcut = distance.create_aligner(None);
+            let mut wfa = distance.create_aligner(None);
+            wfa.set_identical_fast_path(false);
+
+            let seq = b"ACGTTGCAACGT";
+            assert_eq!(shortcut.align(seq, seq), AlignmentStatus::Completed);
+            assert_eq!(wfa.align(seq, seq), AlignmentStatus::Completed);
+            assert_eq!(shortcut.identical_shortcut_count(), 1);
+            assert_eq!(wfa.identical_shortcut_count(), 0);
+
+            assert_eq!(shortcut.cigar(), vec![b'M'; seq.len()].as_slice());
+            assert_eq!(shortcut.cigar(), wfa.cigar());
+            assert_eq!(shortcut.score(), 0);
+            assert_eq!(shortcut.score(), wfa.score());
+            assert_eq!(shortcut.cigar_sam(false), wfa.cigar_sam(false));
+            assert_eq!(shortcut.cigar_sam(true), wfa.cigar_sam(true));
+            assert_eq!(shortcut.cigar_htslib(), wfa.cigar_htslib());
+            assert_eq!(shortcut.alignment_endpoints(), wfa.alignment_endpoints());
+        }
+    }
+
+    #[test]
+    fn identical_shortcut_needs_equal_bytes() {
+        let aligner = gap_affine();
+        assert_eq!(aligner.align(b"ACGT", b"ACGt"), AlignmentStatus::Completed);
+        assert_eq!(aligner.identical_shortcut_count(), 0);
+        assert_eq!(aligner.cigar(), b"MMMX");
+    }
+
+    /// Aligns with `span`, returning the status, CIGAR and score for each metric, edit first.
+    fn align_with_span(
+        span: &AlignmentSpan,
+        a: &[u8],
+        b: &[u8],
+    ) -> [(AlignmentStatus, Vec<u8>, i32); 2] {
+        [Distance::Edit, GAP_AFFINE].map(|distance| {
+            let mut aligner = distance.create_aligner(None);
+            aligner.set_alignment_span(span);
+            let status = aligner.align(a, b);
+            (status, aligner.cigar_owned(), aligner.score())
+        })
+    }
+
+    fn ends_free(begin: i32, end: i32) -> AlignmentSpan {
+        AlignmentSpan::EndsFree {
+            pattern_begin_free: begin,
+            pattern_end_free: end,
+            text_begin_free: begin,
+            text_end_free: end,
+        }
+    }
+
+    #[test]
+    fn empty_against_empty() {
+        for span in [AlignmentSpan::End2End, ends_free(2, 2)] {
+            for (status, cigar, score) in align_with_span(&span, b"", b"") {
+                assert_eq!(status, AlignmentStatus::Completed);
+                assert!(cigar.is_empty());
+                assert_eq!(score, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn empty_against_sequence_end_to_end() {
+        let [edit, affine] = align_with_span(&AlignmentSpan::End2End, b"", b"ACGT");
+        assert_eq!(edit, (AlignmentStatus::Completed, b"IIII".to_vec(), 4));
+        assert_eq!(affine, (AlignmentStatus::Completed, b"IIII".to_vec(), -14));
+
+        let [edit, affine] = align_with_span(&AlignmentSpan::End2End, b"ACG", b"");
+        assert_eq!(edit, (AlignmentStatus::Completed, b"DDD".to_vec(), 3));
+        assert_eq!(affine, (AlignmentStatus::Completed, b"DDD".to_vec(), -12));
+    }
+
+    #[test]
+    fn empty_against_sequence_ends_free() {
+        // Free ends shorter than the sequence: 4 - (1 + 1) bases are charged.
+        let [edit, affine] = align_with_span(&ends_free(1, 1), b"", b"ACGT");
+        assert_eq!(edit, (AlignmentStatus::Completed, b"IIII".to_vec(), 2));
+        assert_eq!(affine, (AlignmentStatus::Completed, b"IIII".to_vec(), -10));
+
+        let [edit, affine] = align_with_span(&ends_free(1, 1), b"ACGT", b"");
+        assert_eq!(edit, (AlignmentStatus::Completed, b"DDDD".to_vec(), 2));
+        assert_eq!(affine, (AlignmentStatus::Completed, b"DDDD".to_vec(), -10));
+
+        // Free ends longer than the sequence: nothing is charged.
+        let [edit, affine] = align_with_span(&ends_free(3, 3), b"", b"ACGT");
+        assert_eq!(edit, (AlignmentStatus::Completed, b"IIII".to_vec(), 0));
+        assert_eq!(affine, (AlignmentStatus::Completed, b"IIII".to_vec(), 0));
+
+        let [edit, affine] = align_with_span(&ends_free(0, 5), b"ACGT", b"");
+        assert_eq!(edit, (AlignmentStatus::Completed, b"DDDD".to_vec(), 0));
+        assert_eq!(affine, (AlignmentStatus::Completed, b"DDDD".to_vec(), 0));
+    }
+
+    #[test]
+    fn overlong_sequences_are_rejected() {
+        let aligner = gap_affine();
+        assert_eq!(aligner.align(b"ACGT", b"AGT"), AlignmentStatus::Completed);
+        let too_long = i32::MAX as usize + 1;
+        assert_eq!(
+            aligner.check_lengths(too_long, 4),
+            Some(AlignmentStatus::TooLong)
+        );
+        assert!(aligner.cigar().is_empty());
+        assert_eq!(aligner.score(), 0);
+        assert_eq!(
+            aligner.check_lengths(4, too_long),
+            Some(AlignmentStatus::TooLong)
+        );
+        assert_eq!(aligner.check_lengths(i32::MAX as usize, 4), None);
+    }
+
+    #[test]
+    #[ignore = "allocates 2 GiB"]
+    fn overlong_sequence_is_rejected_by_align() {
+        let aligner = gap_affine();
+        let pattern = vec![b'A'; i32::MAX as usize + 1];
+        assert_eq!(aligner.align(&pattern, b"A"), AlignmentStatus::TooLong);
+        assert!(aligner.cigar().is_empty());
+    }
+
+    #[test]
+    fn both_strands_keeps_the_winning_alignment() {
+        let aligner = gap_affine();
+        let reference = b"TTTTACGGATCCTTTT";
+        // Reverse complement of the `ACGGAT` substring.
+        assert_eq!(
+            aligner.align_both_strands(b"ATCCGT", &reference[4..10]),
+            (Strand::Reverse, AlignmentStatus::Completed)
+        );
+        assert_eq!(aligner.cigar(), b"MMMMMM");
+        assert_eq!(aligner.score(), 0);
+
+        assert_eq!(
+            aligner.align_both_strands(b"ACGGAT", &reference[4..10]),
+            (Strand::Forward, AlignmentStatus::Completed)
+        );
+        assert_eq!(aligner.cigar(), b"MMMMMM");
+        assert_eq!(aligner.score(), 0);
+    }
+
+    #[test]
+    fn memory_breakdown_reads_every_component() {
+        for (mode, ultralow) in [
+            (MemoryMode::High, false),
+            (MemoryMode::Low, false),
+            (MemoryMode::Ultralow, true),
+        ] {
+            let aligner = AffineWavefrontsBuilder::new()
+                .distance(GAP_AFFINE)
+                .memory_mode(mode)
+                .build();
+            assert_eq!(
+                aligner.align(b"ACGTACGTTGCA", b"ACGTTCGTTGA"),
+                AlignmentStatus::Completed
+            );
+            let breakdown = aligner.memory_breakdown();
+            assert_eq!(breakdown.other, 0);
+            assert_eq!(breakdown.aligner_size(), aligner.get_aligner_size());
+            assert_eq!(breakdown.sub_aligners > 0, ultralow);
+            assert!(breakdown.cigar > 0);
+        }
+    }
+
+    #[test]
+    fn memory_mode_env_values() {
+        assert_eq!(
+            memory_mode_from_env(None),
+            Ok(wfa::wavefront_memory_t_wavefront_memory_high)
+        );
+        assert_eq!(
+            memory_mode_from_env(Some("low")),
+            Ok(wfa::wavefront_memory_t_wavefront_memory_low)
+        );
+        assert_eq!(
+            memory_mode_from_env(Some(" UltraLow ")),
+            Ok(wfa::wavefront_memory_t_wavefront_memory_ultralow)
+        );
+        assert!(memory_mode_from_env(Some("tiny")).is_err());
+    }
+
+    #[test]
+    fn constructors_use_the_default_memory_mode() {
+        for aligner in [
+            AffineWavefronts::default(),
+            Distance::Edit.create_aligner(None),
+            GAP_AFFINE.create_aligner(None),
+        ] {
+            assert_eq!(
+                aligner.get_memory_mode().to_value(),
+                Some(default_memory_mode())
+            );
+        }
+    }
+
+    #[test]
+    fn score_steps_round_trip_through_set_heuristics() {
+        let mut aligner = gap_affine();
+        aligner
+            .set_heuristics(&[
+                HeuristicStrategy::BandedAdaptive {
+                    band_min_k: -10,
+                    band_max_k: 10,
+                    score_steps: 1,
+                },
+                HeuristicStrategy::XDrop {
+                    xdrop: 50,
+                    score_steps: 1,
+                },
+            ])
+            .unwrap();
+        assert_eq!(aligner.get_score_steps(), 1);
+
+        aligner.set_score_steps(5);
+        assert_eq!(aligner.get_score_steps(), 5);
+        assert!(matches!(
+            aligner.get_heuristics().as_slice(),
+            [
+                HeuristicStrategy::XDrop {
+                    xdrop: 50,
+                    score_steps: 5
+                },
+                HeuristicStrategy::BandedAdaptive {
+                    band_min_k: -10,
+                    band_max_k: 10,
+                    score_steps: 5
+                },
+            ]
+        ));
+    }
+
+    #[test]
+    fn heuristics_combine() {
+        let mut aligner = gap_affine();
+        aligner
+            .set_heuristics(&[
+                HeuristicStrategy::BandedStatic {
+                    band_min_k: -8,
+                    band_max_k: 8,
+                },
+                HeuristicStrategy::XDrop {
+                    xdrop: 40,
+                    score_steps: 2,
+                },
+            ])
+            .unwrap();
+        let strategy = unsafe { (*aligner.aligner()).heuristic.strategy };
+        assert_eq!(
+            strategy,
+            wfa::wf_heuristic_strategy_wf_heuristic_banded_static
+                | wfa::wf_heuristic_strategy_wf_heuristic_xdrop
+        );
+        assert!(matches!(
+            aligner.get_heuristics().as_slice(),
+            [
+                HeuristicStrategy::XDrop {
+                    xdrop: 40,
+                    score_steps: 2
+                },
+                HeuristicStrategy::BandedStatic {
+                    band_min_k: -8,
+                    band_max_k: 8
+                },
+            ]
+        ));
+        assert_eq!(
+            aligner.align(b"ACGTACGT", b"ACGTTCGT"),
+            AlignmentStatus::Completed
+        );
+    }
+
+    #[test]
+    fn conflicting_heuristics_are_rejected() {
+        let mut aligner = gap_affine();
+        aligner
+            .set_heuristics(&[HeuristicStrategy::XDrop {
+                xdrop: 40,
+                score_steps: 2,
+            }])
+            .unwrap();
+        let error = aligner
+            .set_heuristics(&[
+                HeuristicStrategy::XDrop {
+                    xdrop: 40,
+                    score_steps: 1,
+                },
+                HeuristicStrategy::ZDrop {
+                    zdrop: 60,
+                    score_steps: 3,
+                },
+            ])
+            .unwrap_err();
+        assert!(error.contains("steps_between_cutoffs"));
+        // The aligner keeps its previous heuristic.
+        assert!(matches!(
+            aligner.get_heuristics().as_slice(),
+            [HeuristicStrategy::XDrop {
+                xdrop: 40,
+                score_steps: 2
+            }]
+        ));
+    }
+
+    #[test]
+    fn set_alignment_scope_keeps_the_configuration() {
+        let mut aligner = AffineWavefrontsBuilder::new()
+            .distance(GAP_AFFINE)
+            .heuristic(HeuristicStrategy::XDrop {
+                xdrop: 100,
+                score_steps: 3,
+            })
+            .memory_mode(MemoryMode::Low)
+            .span(ends_free(2, 3))
+            .build();
+        aligner.set_identical_fast_path(false);
+        aligner.set_gap_preference(Some(GapPreference::DeletionFirst));
+        aligner.set_left_align_indels(true);
+        aligner.set_max_cigar_len(1000);
+        aligner.set_min_alignment_length(4);
+        aligner.set_label("scoped");
+
+        let (a, b) = (b"ACGTACGTTGCA", b"ACGTTCGTTGCA");
+        assert_eq!(aligner.align(a, b), AlignmentStatus::Completed);
+        let full_score = aligner.score();
+        assert!(!aligner.cigar().is_empty());
+        let penalties = aligner.get_penalties();
+
+        aligner.set_alignment_scope(AlignmentScope::ComputeScore);
+        assert!(matches!(
+            aligner.get_alignment_scope(),
+            AlignmentScope::ComputeScore
+        ));
+        assert_eq!(aligner.get_penalties(), penalties);
+        assert!(matches!(
+            aligner.get_heuristics().as_slice(),
+            [HeuristicStrategy::XDrop {
+                xdrop: 100,
+                score_steps: 3
+            }]
+        ));
+        assert_eq!(
+            aligner.get_memory_mode().to_value(),
+            MemoryMode::Low.to_value()
+        );
+        assert!(matches!(
+            aligner.get_alignment_span(),
+            AlignmentSpan::EndsFree {
+                pattern_begin_free: 2,
+                pattern_end_free: 3,
+                text_begin_free: 2,
+                text_end_free: 3,
+            }
+        ));
+        assert!(!aligner.identical_fast_path);
+        assert_eq!(aligner.gap_preference, Some(GapPreference::DeletionFirst));
+        assert!(aligner.left_align_indels);
+        assert_eq!(aligner.max_cigar_len, Some(1000));
+        assert_eq!(aligner.min_alignment_length, 4);
+        assert_eq!(aligner.label(), Some("scoped"));
+
+        assert_eq!(aligner.align(a, b), AlignmentStatus::Completed);
+        assert!(aligner.cigar().is_empty());
+        assert_eq!(aligner.score(), full_score);
+
+        aligner.set_alignment_scope(AlignmentScope::Alignment);
+        assert_eq!(aligner.align(a, b), AlignmentStatus::Completed);
+        assert!(!aligner.cigar().is_empty());
+        assert_eq!(aligner.score(), full_score);
+    }
+
+    #[test]
+    fn edit_distance_known_values() {
+        assert_eq!(edit_distance(b"kitten", b"sitting"), 3);
+        assert_eq!(edit_distance(b"flaw", b"lawn"), 2);
+        assert_eq!(edit_distance(b"ACGTACGT", b"ACGTACGT"), 0);
+        assert_eq!(edit_distance(b"", b""), 0);
+        assert_eq!(edit_distance(b"", b"ACGT"), 4);
+        assert_eq!(edit_distance(b"ACG", b""), 3);
+        assert_eq!(edit_distance(b"AAAA", b"TTTT"), 4);
+        assert_eq!(edit_distance(b"AAAA", b"TTTTTT"), 6);
+    }
+
+    #[test]
+    fn edit_distance_reuses_the_thread_aligner() {
+        // A long pair grows the thread's aligner; later short pairs must not see stale state.
+        let long_a = b"ACGT".repeat(500);
+        let mut long_b = long_a.clone();
+        long_b[1000] = b'T';
+        assert_eq!(edit_distance(&long_a, &long_b), 1);
+        assert_eq!(edit_distance(b"kitten", b"sitting"), 3);
+        assert_eq!(
+            std::thread::spawn(|| edit_distance(b"kitten", b"sitting"))
+                .join()
+                .unwrap(),
+            3
+        );
+    }
+
+    #[test]
+    fn try_new_edit_passes_its_self_check() {
+        let aligner = AffineWavefronts::try_new_edit().unwrap();
+        assert_eq!(aligner.cigar(), b"MDMM");
+        assert_eq!(aligner.score(), 1);
+        assert_eq!(
+            InitError::SelfCheckIncomplete(AlignmentStatus::OOM).to_string(),
+            "WFA2-lib self-check did not complete: OOM"
+        );
+    }
+
+    #[test]
+    fn heuristic_steps_keep_the_other_fields() {
+        let mut aligner = GAP_AFFINE.create_aligner(Some(&HeuristicStrategy::WFAdaptive {
+            min_wavefront_length: 10,
+            max_distance_threshold: 50,
+            score_steps: 1,
+        }));
+        aligner.set_heuristic_steps(7);
+        assert_eq!(aligner.get_heuristic_steps(), 7);
+        assert_eq!(aligner.get_score_steps(), 7);
+        assert!(matches!(
+            aligner.get_heuristics().as_slice(),
+            [HeuristicStrategy::WFAdaptive {
+                min_wavefront_length: 10,
+                max_distance_threshold: 50,
+                score_steps: 7
+            }]
+        ));
+    }
+
+    #[test]
+    fn add_heuristic_stacks_strategies() {
+        let mut aligner = GAP_AFFINE.create_aligner(Some(&HeuristicStrategy::BandedStatic {
+            band_min_k: -8,
+            band_max_k: 8,
+        }));
+        aligner
+            .add_heuristic(&HeuristicStrategy::XDrop {
+                xdrop: 40,
+                score_steps: 2,
+            })
+            .unwrap();
+        assert!(matches!(
+            aligner.get_heuristics().as_slice(),
+            [
+                HeuristicStrategy::XDrop {
+                    xdrop: 40,
+                    score_steps: 2
+                },
+                HeuristicStrategy::BandedStatic {
+                    band_min_k: -8,
+                    band_max_k: 8
+                },
+            ]
+        ));
+
+        let conflict = HeuristicStrategy::BandedAdaptive {
+            band_min_k: -4,
+            band_max_k: 4,
+            score_steps: 2,
+        };
+        assert!(aligner.add_heuristic(&conflict).is_err());
+        assert_eq!(aligner.get_heuristics().len(), 2);
+    }
+
+    #[test]
+    fn free_end_lengths_saturate() {
+        assert_eq!(free_end_length(12), 12);
+        assert_eq!(free_end_length(i32::MAX as usize), i32::MAX);
+        assert_eq!(free_end_length(i32::MAX as usize + 1), i32::MAX);
+        assert_eq!(free_end_length(usize::MAX), i32::MAX);
+    }
+
+    #[test]
+    fn circular_alignment_across_the_origin() {
+        let mut aligner = gap_affine();
+        let reference = b"GGGGACGTCCCCTTAA";
+        let circular = aligner.align_circular(b"TTAAGGGG", reference);
+        assert_eq!(circular.result.status, AlignmentStatus::Completed);
+        assert_eq!(circular.result.score, 0);
+        assert_eq!(circular.result.cigar, b"MMMMMMMM");
+        assert_eq!(circular.reference_start, 12);
+        assert_eq!(circular.reference_end, 4);
+        assert!(circular.spans_origin);
+
+        let linear = aligner.align_circular(b"ACGTCCCC", reference);
+        assert_eq!(linear.result.cigar, b"MMMMMMMM");
+        assert_eq!((linear.reference_start, linear.reference_end), (4, 12));
+        assert!(!linear.spans_origin);
+        assert!(matches!(
+            aligner.get_alignment_span(),
+            AlignmentSpan::End2End
+        ));
+    }
+
+    #[test]
+    fn overlap_between_a_suffix_and_a_prefix() {
+        let mut aligner = gap_affine();
+        let read_a = b"CCCCCCCCACGTTGCAAGTC";
+        let read_b = b"ACGTTGCAAGTCGGGGGGGG";
+        let overlap = aligner.overlap_align(read_a, read_b, 0, 10).unwrap();
+        assert_eq!(overlap.a_range, 8..20);
+        assert_eq!(overlap.b_range, 0..12);
+        assert_eq!(overlap.score, 0);
+        assert_eq!(overlap.cigar, b"MMMMMMMMMMMM");
+
+        // Longer than the true overlap: the reads are forced into a costly alignment instead.
+        let forced = aligner.overlap_align(read_a, read_b, 0, 13).unwrap();
+        assert!(forced.a_range.len() >= 13 && forced.b_range.len() >= 13);
+        assert!(forced.score < 0);
+        assert!(matches!(
+            aligner.get_alignment_span(),
+            AlignmentSpan::End2End
+        ));
+    }
+
+    #[test]
+    fn suffix_alignment_anchors_the_right_ends() {
+        let mut aligner = gap_affine();
+        let result = aligner.align_suffix(b"ACGAACGT", b"TTTTGGGGACGTACGT");
+        assert_eq!(result.status, AlignmentStatus::Completed);
+        assert_eq!(result.score, -4);
+        assert_eq!(result.cigar, b"IIIIIIIIMMMXMMMM");
+        assert!(matches!(
+            aligner.get_alignment_span(),
+            AlignmentSpan::End2End
+        ));
+    }
+
+    #[test]
+    fn paired_reads_are_checked_for_concordance() {
+        let reference = random_sequence(100, 7);
+        let read1 = &reference[10..30];
+        let read2 = reverse_complement(&reference[60..80]);
+        let mut aligner = gap_affine();
+
+        let pair = aligner.align_pair(read1, &read2, &reference, 50..=100);
+        assert_eq!(pair.read1.orientation, Orientation::Forward);
+        assert_eq!(pair.read1.reference_range, 10..30);
+        assert_eq!(pair.read2.orientation, Orientation::Reverse);
+        assert_eq!(pair.read2.reference_range, 60..80);
+        assert_eq!(pair.insert_size, 70);
+        assert!(pair.concordant);
+
+        let too_short = aligner.align_pair(read1, &read2, &reference, 10..=20);
+        assert_eq!(too_short.insert_size, 70);
+        assert!(!too_short.concordant);
+
+        let same_strand = aligner.align_pair(read1, &reference[60..80], &reference, 50..=100);
+        assert_eq!(same_strand.read2.orientation, Orientation::Forward);
+        assert!(!same_strand.concordant);
+        assert!(matches!(
+            aligner.get_alignment_span(),
+            AlignmentSpan::End2End
+        ));
+    }
+
+    #[test]
+    fn edit_script_of_short_strings() {
+        let aligner = gap_affine();
+        aligner.align(b"ACGT", b"AGT");
+        assert_eq!(
+            aligner.edit_script(b"ACGT", b"AGT"),
+            [
+                EditOp::Keep(b'A'),
+                EditOp::Delete(b'C'),
+                EditOp::Keep(b'G'),
+                EditOp::Keep(b'T'),
+            ]
+        );
+
+        aligner.align(b"ACT", b"ACGT");
+        assert_eq!(
+            aligner.edit_script(b"ACT", b"ACGT"),
+            [
+                EditOp::Keep(b'A'),
+                EditOp::Keep(b'C'),
+                EditOp::Insert(b'G'),
+                EditOp::Keep(b'T'),
+            ]
+        );
+
+        aligner.align(b"ACGT", b"ACCT");
+        assert_eq!(
+            aligner.edit_script(b"ACGT", b"ACCT"),
+            [
+                EditOp::Keep(b'A'),
+                EditOp::Keep(b'C'),
+                EditOp::Replace(b'G', b'C'),
+                EditOp::Keep(b'T'),
+            ]
+        );
+        // Sequences shorter than the last alignment do not panic.
+        assert_eq!(
+            aligner.edit_script(b"AC", b""),
+            [
+                EditOp::Keep(b'A'),
+                EditOp::Keep(b'C'),
+                EditOp::Replace(b'?', b'?'),
+                EditOp::Keep(b'?'),
+            ]
+        );
+    }
+
+    #[test]
+    fn homopolymer_indels_in_a_shortened_run() {
+        let aligner = gap_affine();
+        let (query, reference) = (b"ACGTAAAACGT", b"ACGTAAAAAACGT");
+        aligner.align(query, reference);
+        assert_eq!(
+            aligner.homopolymer_indels(query, reference),
+            [HomopolymerEvent {
+                base: b'A',
+                query_pos: 4,
+                reference_pos: 4,
+                query_length: 4,
+                reference_length: 6,
+            }]
+        );
+        assert!(aligner.homopolymer_indels(b"", b"").is_empty());
+
+        // A deleted base between different bases is a random indel.
+        let (query, reference) = (b"ACGTCGTAC", b"ACGTACGTAC");
+        aligner.align(query, reference);
+        assert!(aligner.homopolymer_indels(query, reference).is_empty());
+    }
+
+    #[test]
+    fn compact_diff_merges_close_snps() {
+        let aligner = gap_affine();
+        let reference = b"ACGTTGCAGGATCCATGACTTAGCGATCAG";
+        let mut query = reference.to_vec();
+        query[5] = b'A';
+        query[7] = b'T';
+        query[25] = b'T';
+        aligner.align(&query, reference);
+
+        let regions = aligner.compact_diff(&query, reference, 2);
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions[0].query_range, 3..10);
+        assert_eq!(regions[0].reference_range, 3..10);
+        assert_eq!(regions[0].cigar, b"MMXMXMM");
+        assert_eq!(regions[0].query, &query[3..10]);
+        assert_eq!(regions[0].reference, &reference[3..10]);
+        assert_eq!(regions[1].query_range, 23..28);
+        assert_eq!(regions[1].cigar, b"MMXMM");
+        assert!(aligner.compact_diff(b"", b"", 2).is_empty());
+    }
+
+    #[test]
+    fn homopolymers_have_low_complexity() {
+        let aligner = gap_affine();
+        let homopolymer = b"AAAAAAAAAAAAAAAA";
+        aligner.align(homopolymer, homopolymer);
+        assert_eq!(aligner.alignment_complexity(homopolymer, homopolymer), 0.0);
+        assert_eq!(aligner.alignment_complexity(b"", b""), 0.0);
+
+        let diverse = b"ACGTACGTACGTACGT";
+        aligner.align(diverse, diverse);
+        assert_eq!(aligner.alignment_complexity(diverse, diverse), 2.0);
+    }
+
+    #[test]
+    fn cigar_cap_rejects_long_alignments() {
+        let mut aligner = gap_affine();
+        aligner.set_max_cigar_len(10);
+        assert_eq!(
+            aligner.align(b"ACGTA", b"ACGTA"),
+            AlignmentStatus::Completed
+        );
+        assert_eq!(
+            aligner.align(b"ACGTAC", b"ACGTAC"),
+            AlignmentStatus::CigarTooLong
+        );
+        assert!(aligner.cigar().is_empty());
+
+        // Score-only aligners build no CIGAR.
+        aligner.set_alignment_scope(AlignmentScope::ComputeScore);
+        assert_eq!(
+            aligner.align(b"ACGTAC", b"ACGTAC"),
+            AlignmentStatus::Completed
+        );
+    }
+
+    #[test]
+    fn aligners_move_into_threads() {
+        let text = b"ACGTACGT";
+        let handles: Vec<_> = (0..4)
+            .map(|trimmed| {
+                let aligner = gap_affine();
+                std::thread::spawn(move || {
+                    let status = aligner.align(&text[..text.len() - trimmed], text);
+                    (status, aligner.score())
+                })
+            })
+            .collect();
+        let results: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        for (status, _) in &results {
+            assert_eq!(*status, AlignmentStatus::Completed);
+        }
+        let scores: Vec<i32> = results.iter().map(|&(_, score)| score).collect();
+        assert_eq!(scores, [0, -8, -10, -12]);
+    }
+
+    #[test]
+    fn align_checked_returns_an_owned_alignment() {
+        let aligner = gap_affine();
+        let alignment = aligner.align_checked(b"ACGT", b"AGT").unwrap();
+        assert_eq!(
+            alignment,
+            Alignment {
+                score: -8,
+                cigar: b"MDMM".to_vec(),
+            }
+        );
+        aligner.align(b"ACGT", b"ACCT");
+        assert_eq!(alignment.cigar, b"MDMM");
+    }
+
+    #[test]
+    fn align_checked_reports_an_unattainable_end() {
+        // The end diagonal lies outside the static band, so WFA2-lib runs out of wavefronts.
+        let mut aligner = gap_affine();
+        aligner.set_heuristic(Some(&HeuristicStrategy::BandedStatic {
+            band_min_k: -1,
+            band_max_k: 1,
+        }));
+        assert_eq!(
+            aligner.align_checked(b"ACGT", b"ACGTACGTACGTACGTACGT"),
+            Err(AlignmentError::Status(AlignmentStatus::Unattainable))
+        );
+    }
+
+    #[test]
+    fn memory_modes_agree_on_the_score() {
+        let reference = random_sequence(2000, 1);
+        let mut query = reference.clone();
+        for i in (0..query.len()).step_by(37) {
+            query[i] = if query[i] == b'A' { b'C' } else { b'A' };
+        }
+        query.drain(500..505);
+        query.splice(1200..1200, b"GATTACA".iter().copied());
+
+        let mut scores = Vec::new();
+        for mode in [
+            MemoryMode::High,
+            MemoryMode::Medium,
+            MemoryMode::Low,
+            MemoryMode::Ultralow,
+        ] {
+            let mut aligner = gap_affine();
+            aligner.set_memory_mode(mode.clone());
+            assert_eq!(aligner.get_memory_mode().to_value(), mode.to_value());
+            assert_eq!(
+                aligner.align(&query, &reference),
+                AlignmentStatus::Completed
+            );
+            assert!(aligner.scores_agree());
+            scores.push(aligner.score());
+        }
+        assert!(scores[0] < 0);
+        assert!(
+            scores.iter().all(|&score| score == scores[0]),
+            "{:?}",
+            scores
+        );
+    }
+
+    #[test]
+    fn memory_mode_env_reaches_new_aligners() {
+        const EXPECTED: &str = "LIB_WFA2_MEMORY_MODE_TEST_EXPECTED";
+        // The variable is read once per process, so each value is checked in a child process
+        // that runs only this test.
+        if let Ok(expected) = std::env::var(EXPECTED) {
+            let expected: MemoryMode = expected.parse().unwrap();
+            for aligner in [gap_affine(), AffineWavefronts::default()] {
+                assert_eq!(aligner.get_memory_mode().to_value(), expected.to_value());
+            }
+            return;
+        }
+
+        for (value, expected) in [("low", "low"), ("UltraLow", "ultralow"), ("tiny", "high")] {
+            let output = std::process::Command::new(std::env::current_exe().unwrap())
+                .args([
+                    "--exact",
+                    "affine_wavefront::tests::memory_mode_env_reaches_new_aligners",
+                ])
+                .env(MEMORY_MODE_ENV, value)
+                .env(EXPECTED, expected)
+                .output()
+                .unwrap();
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            assert!(output.status.success(), "{}: {}{}", value, stdout, stderr);
+            assert!(stdout.contains("1 passed"), "{}: {}", value, stdout);
+            assert_eq!(
+                stderr.contains("falling back"),
+                expected == "high",
+                "{}",
+                stderr
+            );
+        }
+    }
+
+    #[test]
+    fn default_is_gap_affine_unlike_the_edit_aligner() {
+        let default = AffineWavefronts::default();
+        assert!(matches!(
+            default.get_distance(),
+            Distance::GapAffine {
+                mismatch: 4,
+                gap_opening: 6,
+                gap_extension: 2,
+            }
+        ));
+        assert!(default.get_heuristics().is_empty());
+        assert!(matches!(
+            default.get_alignment_scope(),
+            AlignmentScope::Alignment
+        ));
+        assert!(matches!(
+            default.get_alignment_span(),
+            AlignmentSpan::End2End
+        ));
+        assert_eq!(default.align(b"ACGT", b"AGT"), AlignmentStatus::Completed);
+        assert_eq!(default.score(), -8);
+
+        let edit = Distance::Edit.create_aligner(None);
+        assert!(matches!(edit.get_distance(), Distance::Edit));
+        assert_eq!(edit.align(b"ACGT", b"AGT"), AlignmentStatus::Completed);
+        assert_eq!(edit.score(), 1);
+        assert_eq!(
+            edit.get_memory_mode().to_value(),
+            default.get_memory_mode().to_value()
+        );
+    }
+
+    #[test]
+    fn align_batch_matches_single_alignments() {
+        let mut owned: Vec<(Vec<u8>, Vec<u8>)> = (0..10_000)
+            .map(|n: u32| {
+                let text = random_sequence(50, n);
+                let mut pattern = text.clone();
+                pattern[n as usize % 50] = b'N';
+                (pattern, text)
+            })
+            .collect();
+        // A long, divergent outlier grows the aligner past four times its size.
+        let text = random_sequence(6000, 10_000);
+        let mut pattern = text.clone();
+        for i in (0..pattern.len()).step_by(4) {
+            pattern[i] = b'N';
+        }
+        owned.insert(5_000, (pattern, text));
+        let pairs: Vec<(&[u8], &[u8])> = owned
+            .iter()
+            .map(|(a, b)| (a.as_slice(), b.as_slice()))
+            .collect();
+
+        let mut aligner = gap_affine();
+        aligner.clear();
+        let limit = aligner.get_aligner_size() * 4;
+        let results = aligner.align_batch(&pairs);
+        assert!(aligner.get_aligner_size() <= limit);
+
+        let single = gap_affine();
+        assert_eq!(results.len(), pairs.len());
+        for (&(a, b), (score, cigar)) in pairs.iter().zip(&results) {
+            assert_eq!(single.align(a, b), AlignmentStatus::Completed);
+            assert_eq!((single.score(), single.cigar()), (*score, cigar.as_slice()));
+        }
+        single.align(pairs[5_000].0, pairs[5_000].1);
+        assert!(single.get_aligner_size() > limit);
+    }
+
+    #[test]
+    fn owned_cigars_survive_the_next_alignment() {
+        let aligner = gap_affine();
+        aligner.align(b"ACGT", b"AGT");
+        let owned = aligner.cigar_owned();
+        let result = aligner.take_result();
+
+        aligner.align(b"ACGTACGT", b"ACGAACGTT");
+        assert_ne!(aligner.cigar(), b"MDMM");
+        assert_eq!(owned, b"MDMM");
+        assert_eq!(result.status, AlignmentStatus::Completed);
+        assert_eq!(result.score, -8);
+        assert_eq!(result.cigar, b"MDMM");
+    }
+
+    #[test]
+    fn label_survives_clone() {
+        let mut aligner = gap_affine();
+        assert_eq!(aligner.label(), None);
+        aligner.set_label("worker-3");
+        let clone = aligner.clone();
+        assert_eq!(clone.label(), Some("worker-3"));
+        assert!(format!("{:?}", clone).contains("worker-3"));
+
+        aligner.set_label("worker-4");
+        assert_eq!(clone.label(), Some("worker-3"));
+    }
+
+    #[test]
+    fn chunked_alignment_within_one_window_matches_align() {
+        let aligner = gap_affine();
+        let (a, b) = (b"ACGTACGTTGCAACGT", b"ACGAACGTTGCCAACGT");
+        let chunked = aligner.align_chunked(a, b, 100, 20);
+        assert_eq!(aligner.align(a, b), AlignmentStatus::Completed);
+        assert_eq!(chunked.status, AlignmentStatus::Completed);
+        assert_eq!(chunked.score, aligner.score());
+        assert_eq!(chunked.cigar, aligner.cigar());
+    }
+
+    #[test]
+    fn heuristic_applies_only_past_the_exact_limit() {
+        // The band excludes the final diagonal of both pairs, so it ruins any pair it prunes.
+        let band = HeuristicStrategy::BandedStatic {
+            band_min_k: -1,
+            band_max_k: 1,
+        };
+        let mut aligner = gap_affine();
+
+        let (a, b) = (b"ACGTACGTAC", b"ACGTACGTACGT");
+        assert_eq!(
+            aligner.align_with_heuristic_after(a, b, &band, 20),
+            AlignmentStatus::Completed
+        );
+        assert_eq!(aligner.score(), -10);
+
+        let (a, b) = (b"ACGT", b"ACGTACGTACGTACGTACGT");
+        assert_eq!(
+            aligner.align_with_heuristic_after(a, b, &band, 20),
+            AlignmentStatus::Unattainable
+        );
+        assert!(aligner.get_heuristics().is_empty());
+        assert_eq!(aligner.align(a, b), AlignmentStatus::Completed);
+        assert_eq!(aligner.score(), -38);
+    }
+
+    #[test]
+    fn score_bounds_of_edit_and_gap_affine() {
+        let edit = Distance::Edit.create_aligner(None);
+        assert_eq!(edit.score_bounds(5, 5), (0, 5));
+        assert_eq!(edit.score_bounds(10, 7), (3, 10));
+        assert_eq!(edit.score_bounds(0, 4), (4, 4));
+        assert_eq!(edit.score_bounds(0, 0), (0, 0));
+
+        // Gaps cost 6 + 2 per base, mismatches 4.
+        let affine = gap_affine();
+        assert_eq!(affine.score_bounds(10, 7), (-12, -40));
+        assert_eq!(affine.score_bounds(2, 2), (0, -8));
+    }
+
+    #[test]
+    fn match_coordinates_skip_gaps_and_mismatches() {
+        let aligner = gap_affine();
+        aligner.align(b"ACGT", b"AGT");
+        assert_eq!(aligner.match_coordinates(), [(0, 0), (2, 1), (3, 2)]);
+        aligner.align(b"ACT", b"ACGT");
+        assert_eq!(aligner.match_coordinates(), [(0, 0), (1, 1), (2, 3)]);
+
+        let (a, b) = (b"ACGTACGTTGCAACGT", b"ACGAACGTTGCCAACGT");
+        aligner.align(a, b);
+        let points = aligner.match_coordinates();
+        let matches = aligner.cigar().iter().filter(|&&op| op == b'M').count();
+        assert_eq!(points.len(), matches);
+        assert!(points
+            .windows(2)
+            .all(|w| w[0].0 < w[1].0 && w[0].1 < w[1].1));
+    }
+
+    #[test]
+    fn soft_masked_tails_are_clipped() {
+        let mut aligner = gap_affine();
+        let (read, reference) = (b"ccACGTaCGTACgg", b"ACGTACGTAC");
+        assert_eq!(soft_masked_ends(read), (2, 2));
+
+        assert_eq!(
+            aligner.align_soft_masked(read, reference),
+            AlignmentStatus::Completed
+        );
+        assert_eq!(aligner.score(), 0);
+        assert_eq!(aligner.cigar(), b"DDMMMMMMMMMMDD");
+        assert_eq!(aligner.suggested_soft_clips(read), (2, 2));
+        assert!(matches!(
+            aligner.get_alignment_span(),
+            AlignmentSpan::End2End
+        ));
+    }
+
+    #[test]
+    fn cigar_runs_walk_from_both_ends() {
+        let cigar = b"MMXIIIMDD";
+        let forward: Vec<_> = CigarOps::new(cigar).collect();
+        assert_eq!(
+            forward,
+            [
+                (CigarOp::Match, 2),
+                (CigarOp::Mismatch, 1),
+                (CigarOp::Insertion, 3),
+                (CigarOp::Match, 1),
+                (CigarOp::Deletion, 2),
+            ]
+        );
+        let mut backward: Vec<_> = CigarOps::new(cigar).rev().collect();
+        backward.reverse();
+        assert_eq!(backward, forward);
+
+        let mut runs = CigarOps::new(cigar);
+        assert_eq!(runs.next(), Some((CigarOp::Match, 2)));
+        assert_eq!(runs.next_back(), Some((CigarOp::Deletion, 2)));
+        assert_eq!(runs.next_back(), Some((CigarOp::Match, 1)));
+        assert_eq!(runs.next(), Some((CigarOp::Mismatch, 1)));
+        assert_eq!(runs.next_back(), Some((CigarOp::Insertion, 3)));
+        assert_eq!(runs.next(), None);
+        assert_eq!(runs.next_back(), None);
+    }
+
+    #[test]
+    fn divergent_pairs_take_more_score_steps() {
+        let aligner = gap_affine();
+        let reference = random_sequence(200, 11);
+        let mut near = reference.clone();
+        near[100] = if near[100] == b'A' { b'C' } else { b'A' };
+        aligner.align(&near, &reference);
+        let near_steps = aligner.score_steps_taken();
+        assert_eq!(near_steps, 4);
+
+        aligner.align(&random_sequence(200, 12), &reference);
+        let divergent_steps = aligner.score_steps_taken();
+        assert_eq!(divergent_steps, aligner.score().unsigned_abs() as u64);
+        assert!(divergent_steps > 10 * near_steps);
+    }
+
+    #[test]
+    fn presets_align_representative_reads() {
+        let reference = random_sequence(2000, 21);
+
+        let short = AlignerConfig::short_read_preset().create_aligner();
+        assert!(matches!(short.get_distance(), Distance::GapAffine { .. }));
+        assert_eq!(short.get_heuristics().len(), 1);
+        let mut read = reference[500..650].to_vec();
+        for i in [40, 110] {
+            read[i] = if read[i] == b'A' { b'C' } else { b'A' };
+        }
+        assert_eq!(
+            short.align(&read, &reference[500..650]),
+            AlignmentStatus::Completed
+        );
+        assert_eq!(short.score(), -8);
+
+        // A 30-base deletion takes the cheaper long-gap penalty, 24 + 30.
+        let long = AlignerConfig::long_read_preset().create_aligner();
+        assert!(matches!(long.get_distance(), Distance::GapAffine2p { .. }));
+        let read = [&reference[..1000], &reference[1030..]].concat();
+        assert_eq!(long.align(&read, &reference), AlignmentStatus::Completed);
+        assert_eq!(long.score(), -54);
+    }
+
+    #[test]
+    fn spans_count_the_bases_each_sequence_consumes() {
+        let aligner = gap_affine();
+        // The query's extra C is a SAM insertion (WFA `D`): query only.
+        aligner.align(b"ACGT", b"AGT");
+        assert_eq!(aligner.cigar(), b"MDMM");
+        assert_eq!((aligner.query_span(), aligner.reference_span()), (4, 3));
+
+        aligner.align(b"ACT", b"ACGT");
+        assert_eq!(aligner.cigar(), b"MMIM");
+        assert_eq!((aligner.query_span(), aligner.reference_span()), (3, 4));
+    }
+
+    #[test]
+    fn result_diff_pinpoints_the_first_difference() {
+        let result = |score: i32, cigar: &[u8]| AlignmentResult {
+            status: AlignmentStatus::Completed,
+            score,
+            cigar: cigar.to_vec(),
+        };
+        let golden = result(-8, b"MDMM");
+        assert_eq!(golden.diff(&result(-8, b"MDMM")), None);
+
+        assert_eq!(
+            golden.diff(&result(-10, b"MMDM")),
+            Some(ResultDiff {
+                status: None,
+                score: Some((-8, -10)),
+                cigar: Some(CigarDiff {
+                    column: 1,
+                    left: Some((CigarOp::Deletion, 1)),
+                    right: Some((CigarOp::Match, 2)),
+                }),
+            })
+        );
+        let truncated = golden.diff(&result(-8, b"MDM")).unwrap();
+        assert_eq!(truncated.score, None);
+        assert_eq!(
+            truncated.cigar,
+            Some(CigarDiff {
+                column: 3,
+                left: Some((CigarOp::Match, 2)),
+                right: None,
+            })
+        );
+    }
+
+    #[test]
+    fn terminal_ns_are_clipped() {
+        let aligner = gap_affine();
+        let clipped = aligner.align_clipping_ns(b"NNACGTNACGTnn", b"ACGTAACGT");
+        assert_eq!((clipped.clip_start, clipped.clip_end), (2, 2));
+        assert_eq!(clipped.query_range(), 2..11);
+        assert_eq!(clipped.result.status, AlignmentStatus::Completed);
+        // The interior N is aligned as a mismatch.
+        assert_eq!(clipped.result.cigar, b"MMMMXMMMM");
+        assert_eq!(clipped.result.score, -4);
+
+        let only_ns = aligner.align_clipping_ns(b"NNN", b"ACG");
+        assert_eq!((only_ns.clip_start, only_ns.clip_end), (3, 0));
+        assert_eq!(only_ns.result.cigar, b"III");
+        assert_eq!(only_ns.result.score, -12);
+    }
+
+    #[test]
+    fn large_deletion_splits_the_alignment() {
+        let aligner = gap_affine();
+        let reference = random_sequence(100, 33);
+        let query = [&reference[..40], &reference[60..]].concat();
+        aligner.align(&query, &reference);
+
+        let blocks = aligner.split_at_large_indels(10);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(
+            (
+                blocks[0].query_range.clone(),
+                blocks[0].reference_range.clone()
+            ),
+            (0..40, 0..40)
+        );
+        assert_eq!(
+            (
+                blocks[1].query_range.clone(),
+                blocks[1].reference_range.clone()
+            ),
+            (40..80, 60..100)
+        );
+        assert!(blocks.iter().all(|block| block.cigar == [b'M'; 40]));
+        assert_eq!(blocks[0].gap_to(&blocks[1]), (0, 20));
+
+        assert_eq!(aligner.split_at_large_indels(21).len(), 1);
+    }
+
+    #[test]
+    fn summary_of_a_known_alignment() {
+        let aligner = gap_affine();
+        aligner.align(b"ACGT", b"AGT");
+        assert_eq!(
+            aligner.summary(),
+            AlignmentSummary {
+                status: AlignmentStatus::Completed,
+                score: -8,
+                matches: 3,
+                mismatches: 0,
+                insertions: 0,
+                deletions: 1,
+                query_span: 4,
+                reference_span: 3,
+                block_identity: 0.75,
+            }
+        );
+        #[cfg(feature = "serde")]
+        assert_eq!(
+            aligner.stats_json(),
+            r#"{"status":"Completed","score":-8,"matches":3,"mismatches":0,"insertions":0,"deletions":1,"query_span":4,"reference_span":3,"block_identity":0.75}"#
+        );
+    }
+
+    #[test]
+    fn gap_preference_orders_adjacent_gaps() {
+        // A mismatch costs more than a one-base insertion plus a one-base deletion.
+        let distance = Distance::GapAffine {
+            mismatch: 20,
+            gap_opening: 6,
+            gap_extension: 2,
+        };
+        let mut aligner = distance.create_aligner(None);
+        for (preference, cigar) in [
+            (GapPreference::InsertionFirst, b"MMIDMM"),
+            (GapPreference::DeletionFirst, b"MMDIMM"),
+        ] {
+            aligner.set_gap_preference(Some(preference));
+            assert_eq!(
+                aligner.align(b"ATGCA", b"ATACA"),
+                AlignmentStatus::Completed
+            );
+            assert_eq!(aligner.cigar(), cigar);
+            assert_eq!(aligner.score(), -16);
+        }
+    }
+
+    #[test]
+    fn normalized_distance_of_known_pairs() {
+        let aligner = gap_affine();
+        aligner.align(b"ACGTACGT", b"ACGTACGT");
+        assert_eq!(aligner.normalized_distance(), 0.0);
+        aligner.align(b"ACGTACGT", b"ACGAACGT");
+        assert_eq!(aligner.normalized_distance(), 0.125);
+        aligner.align(b"ACGT", b"AGT");
+        assert_eq!(aligner.normalized_distance(), 0.25);
+        aligner.align(b"AAAA", b"CCCC");
+        assert_eq!(aligner.normalized_distance(), 1.0);
+    }
+
+    #[test]
+    fn gfa_link_of_a_known_overlap() {
+        let mut aligner = gap_affine();
+        let overlap = aligner
+            .overlap_align(b"CCCCCCCCACGTTGCAAGTC", b"ACGTTGCAAGTCGGGGGGGG", 0, 10)
+            .unwrap();
+        assert_eq!(
+            overlap.to_gfa_overlap("read1", "read2", Orientation::Forward, Orientation::Reverse),
+            "L\tread1\t+\tread2\t-\t12M"
+        );
+
+        let gapped = Overlap {
+            a_range: 0..8,
+            b_range: 0..8,
+            score: -16,
+            cigar: b"MMMDMMIMM".to_vec(),
+        };
+        assert_eq!(
+            gapped.to_gfa_overlap("a", "b", Orientation::Forward, Orientation::Forward),
+            "L\ta\t+\tb\t+\t3M1D2M1I2M"
+        );
+    }
+
+    #[test]
+    fn hamming_fast_path_and_fallback() {
+        let aligner = gap_affine();
+        aligner.align(b"ACGT", b"AGT");
+
+        let fast = aligner.hamming_or_align(b"ACGTACGT", b"ACGAACGT", 1);
+        assert_eq!(fast.status, AlignmentStatus::Completed);
+        assert_eq!(fast.cigar, b"MMMXMMMM");
+        assert_eq!(fast.score, -4);
+        // The fast path never reaches WFA2-lib.
+        assert_eq!(aligner.cigar(), b"MDMM");
+
+        // A shifted repeat has ten mismatches but aligns with two gaps.
+        let shifted = aligner.hamming_or_align(b"ACGTACGTAC", b"CGTACGTACG", 1);
+        assert_eq!(shifted.cigar, b"DMMMMMMMMMI");
+        assert_eq!(shifted.score, -16);
+    }
+
+    #[test]
+    fn translated_alignment_finds_the_coding_frame() {
+        let aligner = gap_affine();
+        let table = CodonTable::standard();
+        let best = aligner.translated_align(b"GATGAAATGGGTTTTT", b"MKWVF", &table, false);
+        assert_eq!(best.frame, 2);
+        assert_eq!(best.translation, b"MKWVF");
+        assert_eq!(best.result.score, 0);
+        assert_eq!(best.result.cigar, b"MMMMM");
+
+        let reverse = aligner.translated_align(b"AAAAACCCATTTCAT", b"MKWVF", &table, true);
+        assert_eq!(reverse.frame, -1);
+        assert_eq!(reverse.result.score, 0);
+    }
+
+    #[test]
+    fn coverage_interval_of_a_placed_read() {
+        let mut aligner = gap_affine();
+        aligner.set_alignment_span(&AlignmentSpan::EndsFree {
+            pattern_begin_free: 0,
+            pattern_end_free: 0,
+            text_begin_free: 10,
+            text_end_free: 10,
+        });
+        let reference = random_sequence(30, 41);
+        aligner.align(&reference[5..25], &reference);
+        assert_eq!(aligner.reference_coverage_interval(), (5, 25));
+        assert_eq!(aligner.sam_pos(100), 106);
+    }
+
+    #[test]
+    fn sparse_scores_drop_pairs_below_the_threshold() {
+        let seqs: [&[u8]; 4] = [b"ACGTACGT", b"ACGAACGT", b"ACGT", b"TTTTTTTT"];
+        let pairs = [(0, 1), (0, 2), (0, 3), (1, 2)];
+
+        let config = AlignerConfig::short_read_preset();
+        assert_eq!(
+            sparse_scores(&config, &seqs, &pairs, -14),
+            [(0, 1, -4), (0, 2, -14), (1, 2, -14)]
+        );
+
+        let edit = AlignerConfig {
+            distance: Distance::Edit,
+            ..config
+        };
+        assert_eq!(sparse_scores(&edit, &seqs, &pairs, 1), [(0, 1, 1)]);
+    }
+
+    #[test]
+    fn path_grazing_the_band_edge_is_detected() {
+        let mut aligner = gap_affine();
+        let (a, b) = (b"ACGTACGT", b"ACGTACGTAA");
+        aligner.align(a, b);
+        assert!(!aligner.path_reached_band_edge());
+
+        // The path ends on diagonal 2, the upper edge of the band.
+        aligner.set_heuristic(Some(&HeuristicStrategy::BandedStatic {
+            band_min_k: -2,
+            band_max_k: 2,
+        }));
+        assert_eq!(aligner.align(a, b), AlignmentStatus::Completed);
+        assert!(aligner.path_reached_band_edge());
+
+        aligner.set_heuristic(Some(&HeuristicStrategy::BandedStatic {
+            band_min_k: -5,
+            band_max_k: 5,
+        }));
+        assert_eq!(aligner.align(a, b), AlignmentStatus::Completed);
+        assert!(!aligner.path_reached_band_edge());
+    }
+
+    #[test]
+    fn scores_agree_until_the_cigar_is_corrupted() {
+        let aligner = gap_affine();
+        let pairs: [(&[u8], &[u8]); 3] = [
+            (b"ACGT", b"AGT"),
+            (b"ACGTACGT", b"ACGAACGTT"),
+            (b"ACT", b"ACGT"),
+        ];
+        for (a, b) in pairs {
+            aligner.align(a, b);
+            assert_eq!(aligner.computed_score(), aligner.wfa_score());
+            assert!(aligner.scores_agree());
+        }
+
+        aligner.align(b"ACGT", b"AGT");
+        unsafe { cigar_operations_mut((*aligner.wf_aligner).cigar)[1] = b'X' };
+        assert_eq!(aligner.cigar(), b"MXMM");
+        assert_eq!((aligner.wfa_score(), aligner.computed_score()), (-8, -4));
+        assert!(!aligner.scores_agree());
+    }
+
+    #[test]
+    fn sam_pos_skips_a_leading_insertion() {
+        let aligner = gap_affine();
+        aligner.align(b"TTACGTACGT", b"ACGTACGT");
+        assert_eq!(aligner.cigar_sam(false), "2I8M");
+        // The inserted read bases take no reference position.
+        assert_eq!(aligner.sam_pos(0), 1);
+        assert_eq!(aligner.sam_pos(10), 11);
+    }
+
+    #[test]
+    fn indels_in_a_repeat_are_left_aligned() {
+        let reference = b"ACGTCACACAGTT";
+        let read = b"ACGTCACAGTT";
+        let leftmost = b"MMMMIIMMMMMMM";
+        for placed in [&b"MMMMMMMMIIMMM"[..], b"MMMMMMIIMMMMM", leftmost] {
+            let mut cigar = placed.to_vec();
+            left_align_indels(&mut cigar, read, reference);
+            assert_eq!(cigar, leftmost);
+        }
+
+        let mut aligner = gap_affine();
+        aligner.set_left_align_indels(true);
+        assert_eq!(aligner.align(read, reference), AlignmentStatus::Completed);
+        assert_eq!(aligner.cigar(), leftmost);
+        let score = aligner.score();
+        assert_eq!(
+            aligner.align(b"ACGTCACAGTA", reference),
+            AlignmentStatus::Completed
+        );
+        assert_eq!(aligner.cigar(), b"MMMMIIMMMMMMX");
+        assert_eq!(aligner.score(), score - 4);
+    }
+
+    #[test]
+    fn score_steps_without_a_heuristic_are_stored_only() {
+        let mut aligner = gap_affine();
+        let (a, b) = (b"ACGTACGTTGCA", b"ACGTTCGTTGA");
+        assert_eq!(aligner.align(a, b), AlignmentStatus::Completed);
+        let (score, cigar) = (aligner.score(), aligner.cigar().to_vec());
+
+        aligner.set_score_steps(7);
+        assert_eq!(aligner.get_score_steps(), 7);
+        assert!(aligner.get_heuristics().is_empty());
+        assert_eq!(aligner.align(a, b), AlignmentStatus::Completed);
+        assert_eq!(aligner.score(), score);
+        assert_eq!(aligner.cigar(), cigar);
+    }
+
+    #[test]
+    fn anchored_alignment_through_a_central_seed_matches_align() {
+        let reference = random_sequence(40, 7);
+        let mut query = reference.clone();
+        query[5] = if query[5] == b'A' { b'C' } else { b'A' };
+        query.remove(32);
+
+        let aligner = gap_affine();
+        let anchored = aligner.align_anchored(&query, &reference, (15, 15, 10));
+        assert!(anchored.status.is_completed());
+        assert_eq!(&anchored.cigar[15..25], b"MMMMMMMMMM");
+
+        assert_eq!(
+            aligner.align(&query, &reference),
+            AlignmentStatus::Completed
+        );
+        assert_eq!(anchored.score, aligner.score());
+        assert_eq!(aligner.score(), -12);
+    }
+
+    #[test]
+    fn log_strings_of_example_pairs() {
+        let aligner = gap_affine();
+        let reference = random_sequence(32, 3);
+        let log_string = |query: &[u8], reference: &[u8]| {
+            assert_eq!(aligner.align(query, reference), AlignmentStatus::Completed);
+            aligner.cigar_log_string()
+        };
+
+        assert_eq!(log_string(&reference, &reference), "=32");
+
+        let mut snp = reference.clone();
+        snp[10] = b'A';
+        assert_eq!(log_string(&snp, &reference), "=10X1=21");
+
+        let mut deleted = reference.clone();
+        deleted.remove(20);
+        assert_eq!(log_string(&reference, &deleted), "=20D1=11");
+
+        let mut inserted = reference.clone();
+        inserted.insert(5, b'T');
+        assert_eq!(log_string(&reference, &inserted), "=5I1=27");
+    }
+
+    #[test]
+    fn band_refinement_matches_exact_alignment_across_a_large_indel() {
+        let reference = random_sequence(300, 11);
+        let query = [&reference[..150], &reference[190..]].concat();
+
+        let exact = gap_affine();
+        assert_eq!(exact.align(&query, &reference), AlignmentStatus::Completed);
+        assert_eq!(exact.score(), -(6 + 2 * 40));
+
+        let mut aligner = GAP_AFFINE.create_aligner(Some(&HeuristicStrategy::WFAdaptive {
+            min_wavefront_length: 10,
+            max_distance_threshold: 50,
+            score_steps: 1,
+        }));
+        let refined = aligner.refine_with_band(&query, &reference, 5);
+        assert!(refined.status.is_completed());
+        assert_eq!(refined.score, exact.score());
+        assert_eq!(refined.cigar.iter().filter(|&&op| op == b'I').count(), 40);
+        assert!(matches!(
+            aligner.get_heuristics().as_slice(),
+            [HeuristicStrategy::WFAdaptive {
+                min_wavefront_length: 10,
+                max_distance_threshold: 50,
+                score_steps: 1,
+            }]
+        ));
+    }
+
+    #[test]
+    fn colinear_blocks_merge_and_out_of_order_blocks_do_not() {
+        let block =
+            |query: std::ops::Range<usize>, reference: std::ops::Range<usize>| AlignedBlock {
+                cigar: vec![b'M'; query.len()],
+                query_range: query,
+                reference_range: reference,
+            };
+        let first = block(0..10, 0..10);
+        let second = block(12..20, 13..21);
+        let out_of_order = block(25..30, 5..10);
+
+        let merged = merge_colinear(&[second.clone(), first.clone(), out_of_order.clone()], 5);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].query_range, 0..20);
+        assert_eq!(merged[0].reference_range, 0..21);
+        assert_eq!(merged[0].cigar, b"MMMMMMMMMMDDIIIMMMMMMMM");
+        assert_eq!(merged[1], out_of_order);
+
+        assert_eq!(
+            merge_colinear(&[first.clone(), second.clone()], 2),
+            [first, second]
+        );
+    }
+
+    #[test]
+    fn minus_strand_sam_fields_are_consistent() {
+        let read = random_sequence(30, 5);
+        let forward = reverse_complement(&read);
+        let mut reference = forward.clone();
+        reference[12] = b'A';
+        reference.insert(20, b'C');
+
+        let aligner = gap_affine();
+        assert_eq!(
+            aligner.align(&forward, &reference),
+            AlignmentStatus::Completed
+        );
+        let strand = SamStrand::new(&read, aligner.cigar(), true);
+        assert_eq!(strand.flag(), 0x10);
+        assert_eq!(strand.seq, forward);
+        assert_eq!(strand.cigar, "20M1D10M");
+
+        // 20M: one mismatch, 1D: the reference-only base, 10M: exact matches.
+        let mismatches = (0..20).filter(|&i| strand.seq[i] != reference[i]).count();
+        assert_eq!(mismatches, 1);
+        assert_eq!(strand.seq[20..], reference[21..]);
+
+        let forward_strand = SamStrand::new(&forward, aligner.cigar(), false);
+        assert_eq!(forward_strand.flag(), 0);
+        assert_eq!(forward_strand.seq, forward);
+        assert_eq!(forward_strand.cigar, strand.cigar);
+    }
+
+    #[test]
+    fn min_alignment_length_rejects_a_spurious_local_hit() {
+        let mut aligner = AffineWavefrontsBuilder::new()
+            .distance(GAP_AFFINE)
+            .span(AlignmentSpan::EndsFree {
+                pattern_begin_free: 0,
+                pattern_end_free: 0,
+                text_begin_free: 40,
+                text_end_free: 40,
+            })
+            .build();
+        let short = b"ACG";
+        let text = b"TTTTTACGTTTTT";
+        assert_eq!(aligner.align(short, text), AlignmentStatus::Completed);
+        assert_eq!(aligner.cigar(), b"IIIIIMMMIIIII");
+
+        aligner.set_min_alignment_length(20);
+        assert_eq!(aligner.align(short, text), AlignmentStatus::TooShort);
+        assert_eq!(aligner.cigar(), b"IIIIIMMMIIIII");
+
+        let read = random_sequence(30, 9);
+        let reference = [&b"TTTTTTTTTT"[..], &read, b"TTTTTTTTTT"].concat();
+        assert_eq!(aligner.align(&read, &reference), AlignmentStatus::Completed);
+        assert_eq!(aligned_block_length(aligner.cigar()), 30);
+    }
+
+    #[test]
+    fn recommended_threads_scale_with_the_batch() {
+        let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+        assert_eq!(recommended_threads(0, 0), 1);
+        assert_eq!(recommended_threads(1, 100), 1);
+        assert_eq!(recommended_threads(100, 150), 1);
+        assert_eq!(recommended_threads(2, 1_000_000), cores.min(2));
+        assert_eq!(recommended_threads(1_000_000, 1_000), cores);
+    }
+
+    #[test]
+    fn cumulative_scores_end_at_the_score() {
+        let edit = Distance::Edit.create_aligner(None);
+        assert_eq!(
+            edit.align(b"ACGTACGT", b"ACGTTCGT"),
+            AlignmentStatus::Completed
+        );
+        assert_eq!(edit.cigar(), b"MMMMXMMM");
+        assert_eq!(edit.cumulative_scores(), [0, 0, 0, 0, 1, 1, 1, 1]);
+        assert_eq!(edit.score(), 1);
+
+        let aligner = gap_affine();
+        let reference = random_sequence(40, 21);
+        let mut query = reference.clone();
+        query.drain(10..13);
+        assert_eq!(
+            aligner.align(&query, &reference),
+            AlignmentStatus::Completed
+        );
+        let scores = aligner.cumulative_scores();
+        assert_eq!(scores.len(), aligner.cigar().len());
+        assert_eq!(scores.last(), Some(&aligner.score()));
+        assert!(scores.windows(2).all(|pair| pair[1] <= pair[0]));
+    }
+
+    #[test]
+    fn huge_penalties_shrink_the_representable_length() {
+        assert_eq!(
+            gap_affine().max_representable_length(),
+            i32::MAX as usize / 8
+        );
+
+        let aligner = Distance::GapAffine {
+            mismatch: 100_000_000,
+            gap_opening: 6,
+            gap_extension: 2,
+        }
+        .create_aligner(None);
+        assert_eq!(aligner.max_representable_length(), 21);
+
+        let sequence = random_sequence(11, 4);
+        assert_eq!(
+            aligner.align_checked(&sequence, &sequence),
+            Err(AlignmentError::ScoreOverflow {
+                length: 22,
+                max: 21
+            })
+        );
+        let alignment = aligner
+            .align_checked(&sequence[1..], &sequence[1..])
+            .unwrap();
+        assert_eq!(alignment.score, 0);
+    }
+
+    #[test]
+    fn free_pattern_ends_leave_the_flanks_unpenalized() {
+        let long = random_sequence(30, 13);
+        let short = &long[10..20];
+        let mut aligner = gap_affine();
+
+        assert_eq!(aligner.align(&long, short), AlignmentStatus::Completed);
+        let end_to_end = aligner.score();
+        assert!(end_to_end < -40);
+
+        aligner.set_alignment_span(&AlignmentSpan::EndsFree {
+            pattern_begin_free: 100,
+            pattern_end_free: 100,
+            text_begin_free: 0,
+            text_end_free: 0,
+        });
+        assert!(matches!(
+            aligner.get_alignment_span(),
+            AlignmentSpan::EndsFree {
+                pattern_begin_free: 100,
+                pattern_end_free: 100,
+                text_begin_free: 0,
+                text_end_free: 0,
+            }
+        ));
+        assert_eq!(aligner.align(&long, short), AlignmentStatus::Completed);
+        assert_eq!(aligner.score(), 0);
+        assert_eq!(
+            aligner.cigar(),
+            [[b'D'; 10], [b'M'; 10], [b'D'; 10]].concat()
+        );
+
+        // Free pattern ends do not free the text: the short sequence as the pattern still pays
+        // for the unaligned text flanks.
+        assert_eq!(aligner.align(short, &long), AlignmentStatus::Completed);
+        assert!(aligner.score() < -40);
+
+        aligner.set_alignment_span(&AlignmentSpan::End2End);
+        assert_eq!(aligner.align(&long, short), AlignmentStatus::Completed);
+        assert_eq!(aligner.score(), end_to_end);
+    }
+
+    #[test]
+    fn typed_cigar_runs_of_known_alignments() {
+        let aligner = gap_affine();
+        let reference = random_sequence(32, 3);
+
+        let mut snp = reference.clone();
+        snp[10] = b'A';
+        assert_eq!(aligner.align(&snp, &reference), AlignmentStatus::Completed);
+        assert_eq!(
+            aligner.cigar_run_length(),
+            [
+                (CigarOp::Match, 10),
+                (CigarOp::Mismatch, 1),
+                (CigarOp::Match, 21)
+            ]
+        );
+        let ops: Vec<CigarOp> = aligner.cigar_ops().collect();
+        assert_eq!(ops.len(), 32);
+        assert_eq!(ops[10], CigarOp::Mismatch);
+
+        let mut deleted = reference.clone();
+        deleted.remove(20);
+        assert_eq!(
+            aligner.align(&reference, &deleted),
+            AlignmentStatus::Completed
+        );
+        assert_eq!(
+            aligner.cigar_run_length(),
+            [
+                (CigarOp::Match, 20),
+                (CigarOp::Deletion, 1),
+                (CigarOp::Match, 11)
+            ]
+        );
+
+        assert_eq!(aligner.align(b"", b""), AlignmentStatus::Completed);
+        assert!(aligner.cigar_run_length().is_empty());
+        assert_eq!(aligner.cigar_ops().count(), 0);
+    }
+
+    #[test]
+    fn aligned_kmers_stop_at_indels() {
+        let aligner = gap_affine();
+        let query = random_sequence(32, 3);
+        let mut reference = query.clone();
+        reference.remove(20);
+        assert_eq!(
+            aligner.align(&query, &reference),
+            AlignmentStatus::Completed
+        );
+        assert_eq!(aligner.cigar_sam(false), "20M1I11M");
+
+        let kmers: Vec<_> = aligner.aligned_kmers(&query, &reference, 5).collect();
+        assert_eq!(kmers.len(), 16 + 7);
+        assert!(kmers[..16].iter().all(|&(i, j, _)| i == j && i <= 15));
+        assert!(kmers[16..].iter().all(|&(i, j, _)| i == j + 1 && i >= 21));
+        for (i, j, kmer) in kmers {
+            assert_eq!(kmer, &query[i..i + 5]);
+            assert_eq!(kmer, &reference[j..j + 5]);
+        }
+    }
+
+    #[test]
+    fn sam_cigar_compacts_the_expanded_cigar() {
+        let aligner = gap_affine();
+        let reference = random_sequence(32, 3);
+        let mut query = reference.clone();
+        query[10] = b'A';
+        let mut text = reference.clone();
+        text.insert(5, b'T');
+
+        assert_eq!(aligner.align(&query, &text), AlignmentStatus::Completed);
+        let expanded = [&b"MMMMMIMMMMMX"[..], &[b'M'; 21]].concat();
+        assert_eq!(aligner.cigar(), expanded);
+        assert_eq!(aligner.cigar_sam(false), "5M1D27M");
+        assert_eq!(aligner.cigar_sam(true), "5=1D5=1X21=");
+
+        assert_eq!(aligner.align(b"", b""), AlignmentStatus::Completed);
+        assert_eq!(aligner.cigar_sam(false), "*");
+        assert_eq!(aligner.cigar_sam(true), "*");
+    }
+
+    #[test]
+    fn short_pairs_agree_with_wfa() {
+        let distances = [
+            Distance::Edit,
+            GAP_AFFINE,
+            Distance::GapAffine2p {
+                mismatch: 4,
+                gap_opening1: 6,
+                gap_extension1: 2,
+                gap_opening2: 24,
+                gap_extension2: 1,
+            },
+        ];
+        for distance in distances {
+            let aligner = distance.create_aligner(None);
+            for seed in 0..60 {
+                let a = random_sequence(seed as usize % 16, seed);
+                let mut b = random_sequence(seed as usize * 7 % 16, seed + 1000);
+                if seed % 2 == 0 {
+                    // Related pairs: `a` with a few bases changed, dropped or added.
+                    b = a.clone();
+                    b.truncate(b.len().saturating_sub(1));
+                    b.insert(b.len() / 2, b'T');
+                    if let Some(base) = b.first_mut() {
+                        *base = b'G';
+                    }
+                }
+
+                let short = aligner.align_short(&a, &b, 16);
+                assert_eq!(aligner.align(&a, &b), AlignmentStatus::Completed);
+                assert!(short.status.is_completed());
+                assert_eq!(short.score, aligner.score(), "{:?} vs {:?}", a, b);
+                assert_eq!(
+                    distance.penalty_to_score(distance.cigar_penalty(&short.cigar)),
+                    short.score
+                );
+                let consumed =
+                    |ops: &[u8]| short.cigar.iter().filter(|op| ops.contains(op)).count();
+                assert_eq!(consumed(b"MXD"), a.len());
+                assert_eq!(consumed(b"MXI"), b.len());
+            }
+        }
+    }
+
+    #[test]
+    fn best_scoring_prefix_stops_before_a_divergent_tail() {
+        let prefix = random_sequence(20, 17);
+        let query = [&prefix[..], b"CCCCCCCCCC"].concat();
+        let reference = [&prefix[..], b"AAAAAAAAAA"].concat();
+
+        let aligner = gap_affine();
+        assert_eq!(
+            aligner.align(&query, &reference),
+            AlignmentStatus::Completed
+        );
+        assert_eq!(aligner.cigar_log_string(), "=20X10");
+        assert_eq!(aligner.best_scoring_prefix(1), (20, 20));
+        assert_eq!(aligner.best_scoring_prefix(3), (20, 60));
+        assert_eq!(aligner.best_scoring_prefix(0), (0, 0));
+    }
+
+    #[test]
+    fn tie_policies_pick_different_local_windows() {
+        // Two local alignments of score 10 with a match bonus of 1: ten matches, then fourteen
+        // matches around a mismatch, separated by ten mismatches.
+        let head = random_sequence(10, 19);
+        let tail = random_sequence(15, 23);
+        let mut query_tail = tail.clone();
+        query_tail[7] = b'C';
+        let query = [&head[..], b"TTTTTTTTTT", &query_tail].concat();
+        let reference = [&head[..], b"GGGGGGGGGG", &tail].concat();
+
+        let aligner = gap_affine();
+        assert_eq!(
+            aligner.align(&query, &reference),
+            AlignmentStatus::Completed
+        );
+        assert_eq!(aligner.cigar_log_string(), "=10X10=7X1=7");
+
+        let leftmost = aligner
+            .local_alignment(1, LocalTiePolicy::Leftmost)
+            .unwrap();
+        assert_eq!(leftmost.columns, 0..10);
+        assert_eq!(leftmost.score, 10);
+        let longest = aligner.local_alignment(1, LocalTiePolicy::Longest).unwrap();
+        assert_eq!(longest.columns, 20..35);
+        assert_eq!(longest.score, 10);
+        assert_eq!(longest.query_range, 20..35);
+        assert_eq!(longest.reference_range, 20..35);
+        let identity = aligner
+            .local_alignment(1, LocalTiePolicy::HighestIdentity)
+            .unwrap();
+        assert_eq!(identity.columns, 0..10);
+    }
+
+    #[test]
+    fn step_cap_stops_a_divergent_pair() {
+        let a = random_sequence(200, 41);
+        let b = random_sequence(200, 42);
+        let mut aligner = gap_affine();
+        assert_eq!(aligner.align(&a, &b), AlignmentStatus::Completed);
+        assert!(aligner.score() < -100);
+
+        aligner.set_max_alignment_steps(10);
+        assert_eq!(aligner.align(&a, &b), AlignmentStatus::MaxStepsReached);
+        assert_eq!(aligner.align(&a, &a), AlignmentStatus::Completed);
+    }
+
+    #[test]
+    fn endpoints_bracket_an_embedded_pattern() {
+        let pattern = random_sequence(20, 51);
+        let text = [
+            random_sequence(15, 52),
+            pattern.clone(),
+            random_sequence(10, 53),
+        ]
+        .concat();
+        let mut aligner = gap_affine();
+        aligner.set_alignment_span(&AlignmentSpan::EndsFree {
+            pattern_begin_free: 0,
+            pattern_end_free: 0,
+            text_begin_free: 30,
+            text_end_free: 30,
+        });
+        assert_eq!(aligner.align(&pattern, &text), AlignmentStatus::Completed);
+        assert_eq!(aligner.score(), 0);
+        assert_eq!(aligner.alignment_endpoints(), (0, 20, 15, 35));
+        assert_eq!(&text[15..35], &pattern[..]);
+    }
+
+    #[test]
+    fn stats_of_a_hand_built_cigar() {
+        let stats = AlignmentStats::from_cigar(b"MMMMXMMIIIMMDMMII");
+        assert_eq!(
+            stats,
+            AlignmentStats {
+                matches: 10,
+                mismatches: 1,
+                insertions: 5,
+                deletions: 1,
+                insertion_events: 2,
+                deletion_events: 1,
+            }
+        );
+        assert_eq!(stats.alignment_length(), 17);
+        assert_eq!(stats.block_identity(), 10.0 / 17.0);
+        assert_eq!(stats.gap_compressed_identity(), 10.0 / 14.0);
+
+        let empty = AlignmentStats::from_cigar(b"");
+        assert_eq!(empty.block_identity(), 0.0);
+        assert_eq!(empty.gap_compressed_identity(), 0.0);
+
+        let aligner = gap_affine();
+        assert_eq!(
+            aligner.align(b"ACGTACGTTGCA", b"ACGTTCGTTGCA"),
+            AlignmentStatus::Completed
+        );
+        assert_eq!(aligner.cigar_stats().mismatches, 1);
+        assert_eq!(aligner.cigar_stats().matches, 11);
+    }
+
+    #[test]
+    fn format_alignment_keeps_the_rows_in_register() {
+        let (pattern, text) = (b"ACGTCTGA", b"ACATCTCGA");
+        let aligner = gap_affine();
+        assert_eq!(aligner.align(pattern, text), AlignmentStatus::Completed);
+        assert_eq!(aligner.cigar(), b"MMXMMMIMM");
+
+        assert_eq!(
+            aligner.format_alignment(pattern, text, 0),
+            "ACGTCT-GA\n|| ||| ||\nACATCTCGA\n"
+        );
+        assert_eq!(
+            aligner.format_alignment(pattern, text, 4),
+            "ACGT\n|| |\nACAT\n\nCT-G\n|| |\nCTCG\n\nA\n|\nA\n"
+        );
+    }
+
+    #[test]
+    fn verify_cigar_reports_the_offending_column() {
+        let (pattern, text) = (b"ACGTCTGA", b"ACATCTCGA");
+        let aligner = gap_affine();
+        assert_eq!(aligner.align(pattern, text), AlignmentStatus::Completed);
+        assert_eq!(aligner.verify_cigar(pattern, text), Ok(()));
+        assert_eq!(
+            aligner.verify_cigar(pattern, &text[..8]),
+            Err(CigarVerifyError::TextOverrun { column: 8 })
+        );
+        assert_eq!(
+            aligner.verify_cigar(pattern, b"ACATCTCGAT"),
+            Err(CigarVerifyError::Unconsumed {
+                pattern_left: 0,
+                text_left: 1
+            })
+        );
+
+        let corrupt = |column: usize, op: u8| unsafe {
+            cigar_operations_mut((*aligner.wf_aligner).cigar)[column] = op;
+        };
+        corrupt(2, b'M');
+        assert_eq!(
+            aligner.verify_cigar(pattern, text),
+            Err(CigarVerifyError::NotAMatch { column: 2 })
+        );
+        corrupt(2, b'X');
+        corrupt(0, b'X');
+        assert_eq!(
+            aligner.verify_cigar(pattern, text),
+            Err(CigarVerifyError::NotAMismatch { column: 0 })
+        );
+        corrupt(0, b'M');
+        corrupt(6, b'Z');
+        assert_eq!(
+            aligner.verify_cigar(pattern, text),
+            Err(CigarVerifyError::InvalidOperation {
+                column: 6,
+                operation: b'Z'
+            })
+        );
+    }
+
+    #[test]
+    fn configurations_round_trip_through_bytes() {
+        let distances = [
+            Distance::Edit,
+            Distance::GapLinear {
+                mismatch: 3,
+                indel: 5,
+            },
+            GAP_AFFINE,
+            Distance::GapAffine2p {
+                mismatch: 4,
+                gap_opening1: 6,
+                gap_extension1: 2,
+                gap_opening2: 24,
+                gap_extension2: 1,
+            },
+        ];
+        for distance in &distances {
+            let bytes = distance.to_bytes();
+            let decoded = Distance::from_bytes(&bytes).unwrap();
+            assert_eq!(decoded.to_bytes(), bytes);
+            assert_eq!(decoded.to_u8(), distance.to_u8());
+            assert!(Distance::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+            assert!(Distance::from_bytes(&[&bytes[..], &[0]].concat()).is_err());
+        }
+        assert!(matches!(
+            Distance::from_bytes(&distances[3].to_bytes()),
+            Ok(Distance::GapAffine2p {
+                mismatch: 4,
+                gap_opening1: 6,
+                gap_extension1: 2,
+                gap_opening2: 24,
+                gap_extension2: 1,
+            })
+        ));
+
+        let config = AlignerConfig {
+            distance: distances[3],
+            heuristic: HeuristicStrategy::BandedAdaptive {
+                band_min_k: -10,
+                band_max_k: 12,
+                score_steps: 3,
+            },
+            span: ends_free(2, 3),
+            memory_mode: Some(MemoryMode::Low),
+            scope: AlignmentScope::ComputeScore,
+        };
+        let bytes = config.to_bytes();
+        let decoded = AlignerConfig::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.to_bytes(), bytes);
+        assert!(matches!(
+            decoded.heuristic,
+            HeuristicStrategy::BandedAdaptive {
+                band_min_k: -10,
+                band_max_k: 12,
+                score_steps: 3,
+            }
+        ));
+        assert!(matches!(
+            decoded.span,
+            AlignmentSpan::EndsFree {
+                pattern_begin_free: 2,
+                pattern_end_free: 3,
+                ..
+            }
+        ));
+        assert!(matches!(decoded.scope, AlignmentScope::ComputeScore));
+        assert_eq!(
+            decoded.memory_mode.map(|mode| mode.to_value()),
+            Some(MemoryMode::Low.to_value())
+        );
+    }
+
+    #[test]
+    fn drop_heuristics_read_back_their_own_values() {
+        let xdrop = GAP_AFFINE.create_aligner(Some(&HeuristicStrategy::XDrop {
+            xdrop: 37,
+            score_steps: 2,
+        }));
+        assert!(matches!(
+            xdrop.get_heuristics().as_slice(),
+            [HeuristicStrategy::XDrop {
+                xdrop: 37,
+                score_steps: 2
+            }]
+        ));
+
+        let zdrop = GAP_AFFINE.create_aligner(Some(&HeuristicStrategy::ZDrop {
+            zdrop: 41,
+            score_steps: 3,
+        }));
+        assert!(matches!(
+            zdrop.get_heuristics().as_slice(),
+            [HeuristicStrategy::ZDrop {
+                zdrop: 41,
+                score_steps: 3
+            }]
+        ));
+    }
+
+    #[test]
+    fn penalties_match_the_constructor_arguments() {
+        let dual = Distance::GapAffine2p {
+            mismatch: 5,
+            gap_opening1: 7,
+            gap_extension1: 3,
+            gap_opening2: 20,
+            gap_extension2: 1,
+        }
+        .create_aligner(None);
+        assert_eq!(
+            dual.get_penalties(),
+            Penalties {
+                mismatch: 5,
+                gap_opening1: 7,
+                gap_extension1: 3,
+                gap_opening2: 20,
+                gap_extension2: 1,
+            }
+        );
+
+        assert_eq!(
+            gap_affine().get_penalties(),
+            Penalties {
+                mismatch: 4,
+                gap_opening1: 6,
+                gap_extension1: 2,
+                gap_opening2: 6,
+                gap_extension2: 2,
+            }
+        );
+        assert_eq!(
+            Distance::Edit.create_aligner(None).get_penalties(),
+            Penalties {
+                mismatch: 1,
+                gap_opening1: 0,
+                gap_extension1: 1,
+                gap_opening2: 0,
+                gap_extension2: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn enabled_plot_is_written_to_a_file() {
+        let path = std::env::temp_dir().join(format!("lib_wfa2_plot_{}.txt", std::process::id()));
+        let mut aligner = gap_affine();
+        assert_eq!(
+            aligner.write_plot(&path).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+
+        aligner.enable_plot(PlotParams::default());
+        assert_eq!(
+            aligner.align(b"ACGTACGTTGCA", b"ACGTTCGTTGA"),
+            AlignmentStatus::Completed
+        );
+        aligner.write_plot(&path).unwrap();
+        let written = std::fs::metadata(&path).unwrap().len();
+        std::fs::remove_file(&path).unwrap();
+        assert!(written > 0);
+    }
+
+    #[test]
+    fn linear_gaps_cost_the_same_per_base() {
+        let linear = Distance::GapLinear {
+            mismatch: 4,
+            indel: 2,
+        };
+        let aligner = linear.create_aligner(None);
+        assert!(matches!(
+            aligner.get_distance(),
+            Distance::GapLinear {
+                mismatch: 4,
+                indel: 2
+            }
+        ));
+        assert!(matches!(
+            Distance::from_u8(linear.to_u8()),
+            Ok(Distance::GapLinear { .. })
+        ));
+
+        let reference = random_sequence(30, 61);
+        let one_gap = [&reference[..10], &reference[11..]].concat();
+        assert_eq!(
+            aligner.align(&one_gap, &reference),
+            AlignmentStatus::Completed
+        );
+        assert_eq!(aligner.score(), -2);
+        let three_gaps = [&reference[..10], &reference[13..]].concat();
+        assert_eq!(
+            aligner.align(&three_gaps, &reference),
+            AlignmentStatus::Completed
+        );
+        assert_eq!(aligner.score(), -6);
+    }
+
+    #[test]
+    fn status_predicates_of_every_variant() {
+        use AlignmentStatus::*;
+        for (status, completed, ok) in [
+            (Completed, true, true),
+            (Partial, false, true),
+            (MaxStepsReached, false, false),
+            (OOM, false, false),
+            (Unattainable, false, false),
+            (Undefined, false, false),
+            (TooShort, false, false),
+            (TooLong, false, false),
+            (CigarTooLong, false, false),
+        ] {
+            assert_eq!(status.is_completed(), completed, "{:?}", status);
+            assert_eq!(status.is_ok(), ok, "{:?}", status);
+        }
+        assert_ne!(Completed, Partial);
+    }
+
+    #[test]
+    fn run_metrics_reflect_a_band() {
+        let a = random_sequence(100, 71);
+        let b = random_sequence(100, 72);
+
+        let exact = gap_affine();
+        assert_eq!(exact.align(&a, &b), AlignmentStatus::Completed);
+        let unbanded = exact.run_metrics();
+        assert_eq!(unbanded.score_steps, exact.score().unsigned_abs() as u64);
+
+        let banded = GAP_AFFINE.create_aligner(Some(&HeuristicStrategy::BandedStatic {
+            band_min_k: -5,
+            band_max_k: 5,
+        }));
+        assert_eq!(banded.align(&a, &b), AlignmentStatus::Completed);
+        let metrics = banded.run_metrics();
+        assert!(metrics.diagonal_range.0 >= -5 && metrics.diagonal_range.1 <= 5);
+        assert!(unbanded.diagonal_range.0 < -5 && unbanded.diagonal_range.1 > 5);
+        assert_ne!(metrics, unbanded);
+    }
+
+    #[test]
+    fn all_vs_all_matrix_is_symmetric() {
+        let first = random_sequence(40, 81);
+        let mut close = first.clone();
+        close[10] = if close[10] == b'A' { b'C' } else { b'A' };
+        let far = random_sequence(40, 82);
+        let seqs: [&[u8]; 4] = [&first, &close, &far, &first];
+
+        let mut aligner = gap_affine();
+        let matrix = aligner.all_vs_all_scores(&seqs);
+        assert_eq!(matrix.len(), 4);
+        for (i, row) in matrix.iter().enumerate() {
+            assert_eq!(row[i], 0);
+            assert_eq!(row.iter().max(), Some(&0));
+            for (j, &score) in row.iter().enumerate() {
+                assert_eq!(score, matrix[j][i]);
+            }
+        }
+        assert_eq!(matrix[0][3], 0);
+        assert_eq!(matrix[0][1], -4);
+        assert!(matrix[0][2] < matrix[0][1]);
+        assert!(matches!(
+            aligner.get_alignment_scope(),
+            AlignmentScope::Alignment
+        ));
+    }
+
+    #[test]
+    fn reversed_alignment_matches_a_manual_reversal() {
+        let aligner = gap_affine();
+        let sequence = random_sequence(20, 91);
+        let reversed: Vec<u8> = sequence.iter().rev().copied().collect();
+
+        assert_eq!(
+            aligner.align(&reversed, &sequence),
+            AlignmentStatus::Completed
+        );
+        let (score, cigar) = (aligner.score(), aligner.cigar().to_vec());
+        assert!(score < 0);
+        assert_eq!(
+            aligner.align_reversed(&sequence, &sequence),
+            AlignmentStatus::Completed
+        );
+        assert_eq!(aligner.score(), score);
+        assert_eq!(aligner.cigar(), cigar);
+
+        assert_eq!(
+            aligner.align_reversed(&reversed, &sequence),
+            AlignmentStatus::Completed
+        );
+        assert_eq!(aligner.score(), 0);
+        assert_eq!(
+            aligner.align_reversed(b"ACGGCA", b"ACGGCA"),
+            AlignmentStatus::Completed
+        );
+        assert_eq!(aligner.score(), 0);
+    }
+
+    #[test]
+    fn reset_restores_the_construction_configuration() {
+        let mut aligner = AffineWavefrontsBuilder::new()
+            .distance(GAP_AFFINE)
+            .heuristic(HeuristicStrategy::XDrop {
+                xdrop: 60,
+                score_steps: 2,
+            })
+            .memory_mode(MemoryMode::Medium)
+            .build();
+
+        aligner.set_heuristic(Some(&HeuristicStrategy::BandedStatic {
+            band_min_k: -3,
+            band_max_k: 3,
+        }));
+        aligner.set_memory_mode(MemoryMode::Low);
+        aligner.set_alignment_span(&ends_free(4, 4));
+        assert!(matches!(
+            aligner.get_heuristics().as_slice(),
+            [HeuristicStrategy::BandedStatic { .. }]
+        ));
+
+        aligner.reset();
+        assert!(matches!(
+            aligner.get_heuristics().as_slice(),
+            [HeuristicStrategy::XDrop {
+                xdrop: 60,
+                score_steps: 2
+            }]
+        ));
+        assert_eq!(
+            aligner.get_memory_mode().to_value(),
+            MemoryMode::Medium.to_value()
+        );
+        assert!(matches!(
+            aligner.get_alignment_span(),
+            AlignmentSpan::End2End
+        ));
+        assert_eq!(
+            aligner.align(b"ACGTACGT", b"ACGTTCGT"),
+            AlignmentStatus::Completed
+        );
+        assert_eq!(aligner.score(), -4);
+    }
+
+    #[test]
+    fn htslib_tuples_span_the_query_and_the_reference() {
+        let spans = |tuples: &[(u32, char)]| {
+            let sum = |ops: &str| -> u32 {
+                tuples
+                    .iter()
+                    .filter(|(_, op)| ops.contains(*op))
+                    .map(|(length, _)| length)
+                    .sum()
+            };
+            (sum("M=XI"), sum("M=XD"))
+        };
+        let aligner = gap_affine();
+
+        let (query, reference) = (b"ACGTCTGA", b"ACATCTCGA");
+        assert_eq!(aligner.align(query, reference), AlignmentStatus::Completed);
+        let tuples = aligner.cigar_htslib();
+        assert_eq!(tuples, [(2, '='), (1, 'X'), (3, '='), (1, 'D'), (2, '=')]);
+        assert_eq!(spans(&tuples), (8, 9));
+
+        assert_eq!(aligner.align(reference, query), AlignmentStatus::Completed);
+        let tuples = aligner.cigar_htslib();
+        assert_eq!(tuples, [(2, '='), (1, 'X'), (3, '='), (1, 'I'), (2, '=')]);
+        assert_eq!(spans(&tuples), (9, 8));
+
+        assert_eq!(aligner.align(b"", b""), AlignmentStatus::Completed);
+        assert!(aligner.cigar_htslib().is_empty());
+    }
+
+    #[test]
+    fn config_snapshot_of_a_fully_configured_aligner() {
+        let mut aligner = AffineWavefrontsBuilder::new()
+            .distance(Distance::GapAffine2p {
+                mismatch: 5,
+                gap_opening1: 7,
+                gap_extension1: 3,
+                gap_opening2: 20,
+                gap_extension2: 1,
+            })
+            .memory_mode(MemoryMode::Low)
+            .scope(AlignmentScope::ComputeScore)
+            .span(ends_free(2, 3))
+            .build();
+        aligner
+            .set_heuristics(&[
+                HeuristicStrategy::BandedStatic {
+                    band_min_k: -8,
+                    band_max_k: 8,
+                },
+                HeuristicStrategy::ZDrop {
+                    zdrop: 70,
+                    score_steps: 4,
+                },
+            ])
+            .unwrap();
+
+        let snapshot = aligner.config_snapshot();
+        assert!(matches!(
+            snapshot.distance,
+            Distance::GapAffine2p {
+                mismatch: 5,
+                gap_opening1: 7,
+                gap_extension1: 3,
+                gap_opening2: 20,
+                gap_extension2: 1,
+            }
+        ));
+        assert_eq!(
+            snapshot.penalties,
+            Penalties {
+                mismatch: 5,
+                gap_opening1: 7,
+                gap_extension1: 3,
+                gap_opening2: 20,
+                gap_extension2: 1,
+            }
+        );
+        assert!(matches!(
+            snapshot.heuristics.as_slice(),
+            [
+                HeuristicStrategy::ZDrop {
+                    zdrop: 70,
+                    score_steps: 4
+                },
+                HeuristicStrategy::BandedStatic {
+                    band_min_k: -8,
+                    band_max_k: 8
+                },
+            ]
+        ));
+        assert!(matches!(snapshot.scope, AlignmentScope::ComputeScore));
+        assert!(matches!(
+            snapshot.span,
+            AlignmentSpan::EndsFree {
+                pattern_begin_free: 2,
+                pattern_end_free: 3,
+                text_begin_free: 2,
+                text_end_free: 3,
+            }
+        ));
+        assert_eq!(snapshot.memory_mode.to_value(), MemoryMode::Low.to_value());
+    }
+
+    #[test]
+    fn protein_sequences_align_as_raw_bytes() {
+        let aligner = gap_affine();
+        let first = Sequence::new(b"MKTAYIAKQRQISFVKSHFSRQ").unwrap();
+        let substituted = Sequence::new(b"MKTAYIAKQRQLSFVKSHFSRQ").unwrap();
+        assert_eq!(
+            aligner.align_typed(first, substituted),
+            AlignmentStatus::Completed
+        );
+        assert_eq!(aligner.score(), -4);
+        assert_eq!(aligner.cigar_log_string(), "=11X1=10");
+
+        let inserted = Sequence::new(b"MKTAYIAKQRQISFWVKSHFSRQ").unwrap();
+        assert_eq!(
+            aligner.align_typed(first, inserted),
+            AlignmentStatus::Completed
+        );
+        assert_eq!(aligner.score(), -8);
+        assert_eq!(aligner.cigar_log_string(), "=14I1=8");
+
+        assert!(Sequence::new(b"MKT!AY").is_err());
+        assert!(Sequence::new(b"MKT?AY").is_err());
+    }
+
+    #[test]
+    fn chunked_alignment_of_a_long_pair_matches_align() {
+        let reference = random_sequence(2000, 101);
+        let mut query = reference.clone();
+        for at in [150, 450, 1250, 1700] {
+            query[at] = if query[at] == b'A' { b'C' } else { b'A' };
+        }
+        // Applied from the end so earlier positions do not move.
+        query.insert(1500, b'G');
+        query.remove(800);
+
+        let aligner = gap_affine();
+        let chunked = aligner.align_chunked(&query, &reference, 300, 100);
+        assert_eq!(
+            aligner.align(&query, &reference),
+            AlignmentStatus::Completed
+        );
+        assert_eq!(aligner.score(), -(4 * 4 + 8 + 8));
+
+        assert_eq!(chunked.status, AlignmentStatus::Completed);
+        assert_eq!(chunked.score, aligner.score());
+        assert_eq!(cigar_spans(&chunked.cigar), (query.len(), reference.len()));
+        assert_eq!(
+            AlignmentStats::from_cigar(&chunked.cigar),
+            aligner.cigar_stats()
+        );
+    }
+
+    #[test]
+    fn extended_cigar_writes_matches_as_equals() {
+        let (pattern, text) = (b"ACGTCTGA", b"ACATCTCGA");
+        let aligner = gap_affine();
+        assert_eq!(aligner.align(pattern, text), AlignmentStatus::Completed);
+        assert_eq!(aligner.cigar(), b"MMXMMMIMM");
+        assert_eq!(aligner.cigar_extended(), b"==X===I==");
+
+        let scores = AffineWavefrontsBuilder::new()
+            .distance(GAP_AFFINE)
+            .scope(AlignmentScope::ComputeScore)
+            .build();
+        assert_eq!(scores.align(pattern, text), AlignmentStatus::Completed);
+        assert!(scores.cigar_extended().is_empty());
+    }
+
+    #[test]
+    fn clone_owns_its_aligner() {
+        let aligner = gap_affine();
+        let copy = aligner.clone();
+        assert_ne!(aligner.aligner(), copy.aligner());
+
+        assert_eq!(aligner.align(b"ACGT", b"AGT"), AlignmentStatus::Completed);
+        assert_eq!(copy.align(b"ACGT", b"ACCT"), AlignmentStatus::Completed);
+        assert_eq!(aligner.cigar(), b"MDMM");
+        assert_eq!(aligner.score(), -8);
+        assert_eq!(copy.cigar(), b"MMXM");
+        assert_eq!(copy.score(), -4);
+    }
+
+    #[test]
+    fn clone_then_drop_original_first() {
+        let aligner = gap_affine();
+        let copy = aligner.clone();
+        drop(aligner);
+        assert_eq!(copy.align(b"ACGT", b"AGT"), AlignmentStatus::Completed);
+        drop(copy);
+    }
+
+    #[test]
+    fn clone_then_drop_clone_first() {
+        let aligner = gap_affine();
+        let copy = aligner.clone();
+        drop(copy);
+        assert_eq!(aligner.align(b"ACGT", b"AGT"), AlignmentStatus::Completed);
+        drop(aligner);
     }
 }
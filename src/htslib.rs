@@ -0,0 +1,177 @@
+//! Adapter to realign `rust-htslib` BAM records with WFA (requires the `htslib` feature).
+//!
+//! Records are aligned with the query as the WFA pattern and the reference as the text, so
+//! WFA's `I` (text only) becomes a SAM deletion and `D` (pattern only) a SAM insertion.
+use crate::affine_wavefront::{AlignerConfig, AlignmentStatus};
+use rust_htslib::bam::record::{Cigar, CigarString, Record};
+
+/// Converts a WFA CIGAR (one operation per column) to an htslib CIGAR, with the query as
+/// pattern. Matches and mismatches become `M`.
+pub fn wfa_to_htslib(cigar: &[u8]) -> CigarString {
+    let ops = cigar.chunk_by(|a, b| a == b).filter_map(|run| {
+        let length = run.len() as u32;
+        match run[0] {
+            b'M' | b'X' => Some(Cigar::Match(length)),
+            b'I' => Some(Cigar::Del(length)),
+            b'D' => Some(Cigar::Ins(length)),
+            _ => None,
+        }
+    });
+    CigarString(merge_matches(ops))
+}
+
+/// Converts an htslib CIGAR to a WFA CIGAR, with the query as pattern.
+///
+/// `M` and `=` become WFA matches and `X` mismatches; skipped reference (`N`) is treated as a
+/// deletion. Clips and padding do not appear in WFA CIGARs and are dropped.
+pub fn htslib_to_wfa(cigar: &CigarString) -> Vec<u8> {
+    let mut wfa_cigar = Vec::new();
+    for op in cigar.iter() {
+        let (symbol, length) = match *op {
+            Cigar::Match(length) | Cigar::Equal(length) => (b'M', length),
+            Cigar::Diff(length) => (b'X', length),
+            Cigar::Ins(length) => (b'D', length),
+            Cigar::Del(length) | Cigar::RefSkip(length) => (b'I', length),
+            Cigar::SoftClip(_) | Cigar::HardClip(_) | Cigar::Pad(_) => continue,
+        };
+        wfa_cigar.extend(std::iter::repeat_n(symbol, length as usize));
+    }
+    wfa_cigar
+}
+
+/// Realigns a mapped record end to end against `reference`, the reference bases its current
+/// alignment covers (from `record.pos()` to the end of its CIGAR), and returns the new CIGAR.
+///
+/// Soft and hard clips at the ends of the current CIGAR are kept and only the aligned part of the
+/// query is realigned, so the result spans the same query and reference bases and can be
+/// written back with the same position.
+pub fn realign_record(
+    record: &Record,
+    reference: &[u8],
+    config: &AlignerConfig,
+) -> Result<CigarString, String> {
+    if record.is_unmapped() {
+        return Err("Cannot realign an unmapped record".to_string());
+    }
+    let cigar = record.cigar().take();
+    let ops = cigar.0.as_slice();
+    let leading = ops.iter().take_while(|op| is_clip(op)).count();
+    let trailing = ops[leading..]
+        .iter()
+        .rev()
+        .take_while(|op| is_clip(op))
+        .count();
+    let (head, tail) = (&ops[..leading], &ops[ops.len() - trailing..]);
+
+    let soft_clipped = |clips: &[Cigar]| -> usize {
+        clips
+            .iter()
+            .map(|op| match op {
+                Cigar::SoftClip(length) => *length as usize,
+                _ => 0,
+            })
+            .sum()
+    };
+    let sequence = record.seq().as_bytes();
+    let query_start = soft_clipped(head);
+    let query_end = sequence.len().saturating_sub(soft_clipped(tail));
+    if query_start > query_end {
+        return Err("Soft clips are longer than the record's sequence".to_string());
+    }
+
+    let aligner = config.create_aligner();
+    let status = aligner.align(&sequence[query_start..query_end], reference);
+    if status != AlignmentStatus::Completed {
+        return Err(format!("Realignment did not complete: {:?}", status));
+    }
+
+    let mut ops = head.to_vec();
+    ops.extend(wfa_to_htslib(aligner.cigar()).0);
+    ops.extend_from_slice(tail);
+    Ok(CigarString(ops))
+}
+
+fn is_clip(op: &Cigar) -> bool {
+    matches!(op, Cigar::SoftClip(_) | Cigar::HardClip(_))
+}
+
+/// Joins consecutive `M` operations, which appear wherever WFA matches and mismatches alternate.
+fn merge_matches(ops: impl Iterator<Item = Cigar>) -> Vec<Cigar> {
+    let mut merged: Vec<Cigar> = Vec::new();
+    for op in ops {
+        match (merged.last_mut(), op) {
+            (Some(Cigar::Match(previous)), Cigar::Match(length)) => *previous += length,
+            _ => merged.push(op),
+        }
+    }
+    merged
+}
+
+#[cfg(all(test, feature = "htslib"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cigars_convert_both_ways() {
+        let htslib = CigarString(vec![
+            Cigar::Match(4),
+            Cigar::Del(2),
+            Cigar::Match(1),
+            Cigar::Ins(2),
+            Cigar::Match(1),
+        ]);
+        assert_eq!(wfa_to_htslib(b"MMXMIIMDDM"), htslib);
+        assert_eq!(htslib_to_wfa(&htslib), b"MMMMIIMDDM");
+        assert_eq!(wfa_to_htslib(&htslib_to_wfa(&htslib)), htslib);
+
+        let explicit = CigarString(vec![
+            Cigar::SoftClip(2),
+            Cigar::Equal(3),
+            Cigar::Diff(1),
+            Cigar::RefSkip(2),
+            Cigar::HardClip(1),
+        ]);
+        assert_eq!(htslib_to_wfa(&explicit), b"MMMXII");
+        assert_eq!(
+            wfa_to_htslib(b"MMMXII"),
+            CigarString(vec![Cigar::Match(4), Cigar::Del(2)])
+        );
+    }
+
+    #[test]
+    fn realignment_keeps_the_clips() {
+        let cigar = CigarString(vec![
+            Cigar::HardClip(3),
+            Cigar::SoftClip(2),
+            Cigar::Match(4),
+            Cigar::Del(1),
+            Cigar::Match(3),
+            Cigar::SoftClip(1),
+            Cigar::HardClip(2),
+        ]);
+        let sequence = b"GGACGTCGTT";
+        let mut record = Record::new();
+        record.set(b"read", Some(&cigar), sequence, &[30; 10]);
+        record.set_pos(100);
+        record.unset_unmapped();
+
+        let realigned =
+            realign_record(&record, b"ACGTACGT", &AlignerConfig::short_read_preset()).unwrap();
+        assert_eq!(realigned, cigar);
+
+        // A mismatching reference changes the aligned part only.
+        let realigned =
+            realign_record(&record, b"ACGTCGTA", &AlignerConfig::short_read_preset()).unwrap();
+        assert_eq!(
+            realigned,
+            CigarString(vec![
+                Cigar::HardClip(3),
+                Cigar::SoftClip(2),
+                Cigar::Match(7),
+                Cigar::Del(1),
+                Cigar::SoftClip(1),
+                Cigar::HardClip(2),
+            ])
+        );
+    }
+}
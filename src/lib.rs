@@ -5,3 +5,5 @@ pub mod affine_wavefront;
 #[allow(non_camel_case_types)]
 #[allow(unused)]
 pub mod bindings;
+#[cfg(feature = "htslib")]
+pub mod htslib;